@@ -0,0 +1,62 @@
+use conapp::*;
+
+// the shade blocks (░▒▓) of the default font
+const WATER: Glyph = 176;
+// the arrows (↑↓→←)
+const SPINNER: Glyph = 24;
+
+struct MainScreen {
+    con: Console,
+}
+
+impl MainScreen {
+    pub fn new() -> Box<Self> {
+        let con = Console::new(40, 30, "DEFAULT");
+        Box::new(MainScreen { con })
+    }
+}
+
+impl Screen for MainScreen {
+    fn setup(&mut self, app: &mut AppContext) {
+        if let Some(font) = app.get_font("DEFAULT") {
+            font.animate(GlyphAnimation::new(WATER, 3, 300)).unwrap();
+            font.animate(GlyphAnimation::new(SPINNER, 4, 150).lockstep(true))
+                .unwrap();
+        }
+    }
+
+    fn input(&mut self, _app: &mut AppContext, ev: &AppEvent) -> ScreenResult {
+        match ev {
+            AppEvent::KeyDown(_) => ScreenResult::Quit,
+            AppEvent::MouseDown(_) => ScreenResult::Quit,
+            _ => ScreenResult::Continue,
+        }
+    }
+
+    fn render(&mut self, app: &mut AppContext) {
+        let buffer = self.con.buffer_mut();
+
+        // the cells keep the first glyph - the renderer shows the current frame
+        buffer.fill(Some(0), Some(WHITE), Some(BLACK));
+        buffer.area(
+            5,
+            5,
+            30,
+            15,
+            Some(WATER),
+            Some(RGBA::rgb(64, 128, 255)),
+            Some(RGBA::rgb(0, 0, 96)),
+        );
+        buffer.draw(5, 24, SPINNER, WHITE, BLACK);
+        draw::plain(buffer).print(7, 24, "Loading...");
+
+        self.con.render(app);
+    }
+}
+
+fn main() {
+    let app = AppBuilder::new(1024, 768)
+        .title("Animated Glyphs Example")
+        .build();
+    app.run_screen(MainScreen::new());
+}
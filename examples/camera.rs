@@ -0,0 +1,83 @@
+use conapp::*;
+
+const MAP_WIDTH: i32 = 200;
+const MAP_HEIGHT: i32 = 120;
+
+struct MainScreen {
+    // one cell larger than the view, for the scrolling
+    con: Console,
+    // the player position in map cells
+    player: (f32, f32),
+}
+
+impl MainScreen {
+    pub fn new() -> Box<Self> {
+        // whole pixel scaling, so the scrolling does not shimmer
+        let con = Console::new(41, 26, "DEFAULT")
+            .with_camera_offset(0.0, 0.0)
+            .with_scale(ScalePolicy::Integer);
+        Box::new(MainScreen {
+            con,
+            player: (100.0, 60.0),
+        })
+    }
+}
+
+// a map of trees and grass
+fn map_glyph(x: i32, y: i32) -> Option<(Glyph, RGBA)> {
+    if x < 0 || y < 0 || x >= MAP_WIDTH || y >= MAP_HEIGHT {
+        return None;
+    }
+    match (x * 7 + y * 13) % 11 {
+        0 => Some((6, RGBA::rgb(0, 160, 0))),
+        _ => Some(('.' as Glyph, RGBA::rgb(64, 96, 64))),
+    }
+}
+
+impl Screen for MainScreen {
+    fn input(&mut self, _app: &mut AppContext, ev: &AppEvent) -> ScreenResult {
+        match ev {
+            AppEvent::KeyDown(_) => ScreenResult::Quit,
+            AppEvent::MouseDown(_) => ScreenResult::Quit,
+            _ => ScreenResult::Continue,
+        }
+    }
+
+    fn render(&mut self, app: &mut AppContext) {
+        // the player walks in a circle, the camera follows smoothly
+        let time = app.time() as f32;
+        self.player = (
+            100.0 + (time * 0.5).cos() * 30.0,
+            60.0 + (time * 0.5).sin() * 20.0,
+        );
+        let (left, top) = self.con.follow(self.player.0, self.player.1);
+
+        let buffer = self.con.buffer_mut();
+        buffer.fill(Some(0), Some(BLACK), Some(BLACK));
+        for y in 0..buffer.height() as i32 {
+            for x in 0..buffer.width() as i32 {
+                if let Some((glyph, fg)) = map_glyph(left + x, top + y) {
+                    buffer.draw(x, y, glyph, fg, BLACK);
+                }
+            }
+        }
+
+        // the player is a sprite, so it moves between the cells too
+        self.con.clear_sprites();
+        self.con.add_sprite(
+            Sprite::new(
+                '@' as Glyph,
+                self.player.0 - left as f32,
+                self.player.1 - top as f32,
+            )
+            .fg(RGBA::rgb(255, 255, 0)),
+        );
+
+        self.con.render(app);
+    }
+}
+
+fn main() {
+    let app = AppBuilder::new(1024, 768).title("Camera Example").build();
+    app.run_screen(MainScreen::new());
+}
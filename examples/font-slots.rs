@@ -0,0 +1,81 @@
+use conapp::*;
+
+const TEXT_FONT: &str = "resources/terminal_8x8.png";
+const TILE_FONT: &str = "resources/Runeset_24x24.png";
+
+// The font slot for the tiles (slot 0 is the console's font)
+const TILES: u8 = 1;
+
+struct MainScreen {
+    con: Console,
+    pos: (i32, i32),
+}
+
+impl MainScreen {
+    fn new() -> Box<Self> {
+        let con = Console::new(40, 30, TEXT_FONT).with_font_slot(TILES, TILE_FONT);
+        let pos = (20, 10);
+        Box::new(MainScreen { con, pos })
+    }
+}
+
+impl Screen for MainScreen {
+    fn input(&mut self, _app: &mut AppContext, ev: &AppEvent) -> ScreenResult {
+        match ev {
+            AppEvent::KeyDown(key_down) => match key_down.key_code {
+                VirtualKeyCode::Left => self.pos.0 = (self.pos.0 - 1).max(0),
+                VirtualKeyCode::Right => {
+                    self.pos.0 = (self.pos.0 + 1).min(self.con.width() as i32 - 1)
+                }
+                VirtualKeyCode::Up => self.pos.1 = (self.pos.1 - 1).max(0),
+                VirtualKeyCode::Down => self.pos.1 = (self.pos.1 + 1).min(19),
+                _ => return ScreenResult::Quit,
+            },
+            AppEvent::MouseDown(_) => return ScreenResult::Quit,
+            _ => {}
+        }
+        ScreenResult::Continue
+    }
+
+    fn render(&mut self, app: &mut AppContext) {
+        let buffer = self.con.buffer_mut();
+
+        // the map uses the tiles
+        buffer.fill(
+            Some('.' as u32),
+            Some(RGBA::rgb(64, 64, 64)),
+            Some(RGBA::rgb(0, 0, 0)),
+        );
+        buffer.draw(
+            self.pos.0,
+            self.pos.1,
+            '@' as Glyph,
+            RGBA::rgb(255, 255, 0),
+            RGBA::rgb(0, 0, 0),
+        );
+        // drawing a glyph puts it back in the console's font, so the slot is set after
+        buffer.font_area(0, 0, 40, 20, TILES);
+
+        // the text below it uses the console's font
+        draw::colored(buffer).fg(RGBA::rgb(255, 255, 255)).print(
+            2,
+            22,
+            "Use the #[#F00]arrow keys#[] to move the '#[#FF0]@#[]' symbol around.",
+        );
+        draw::plain(buffer)
+            .fg(RGBA::rgb(0, 192, 192))
+            .font(TILES)
+            .print(2, 25, "Tiles");
+
+        self.con.render(app);
+    }
+}
+
+fn main() {
+    let app = AppBuilder::new(1024, 768)
+        .title("Font Slots Example")
+        .font(TEXT_FONT)
+        .font(TILE_FONT)
+        .build();
+    app.run_screen(MainScreen::new());
+}
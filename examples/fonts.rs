@@ -136,7 +136,9 @@ impl Screen for MyRoguelike {
 fn main() {
     let app = AppBuilder::new(1024, 768)
         .title("Input Example")
-        .fonts(&FONTS)
+        .font(FONTS[0])
+        .font_with_info(FONTS[1], "resources/terminal_colored_8x8.font")
+        .fonts(&FONTS[2..])
         .build();
 
     app.run_screen(MyRoguelike::new());
@@ -0,0 +1,62 @@
+use conapp::*;
+
+struct MainScreen {
+    con: Console,
+    // the index of the spinning star sprite
+    star: usize,
+    // the index of the arrow sprite
+    arrow: usize,
+}
+
+impl MainScreen {
+    pub fn new() -> Box<Self> {
+        let mut con = Console::new(40, 25, "DEFAULT");
+        let star = con.add_sprite(Sprite::new('*' as Glyph, 2.0, 5.0).fg(RGBA::rgb(255, 255, 0)));
+        let arrow = con.add_sprite(
+            Sprite::new(26, 2.0, 12.0)
+                .fg(RGBA::rgb(255, 64, 64))
+                .scale(2.0)
+                .z(1),
+        );
+        con.add_sprite(
+            Sprite::new('@' as Glyph, 0.0, 0.0)
+                .pixel_pos(156.0, 148.0)
+                .bg(RGBA::rgb(0, 0, 128))
+                .alpha(0.5)
+                .z(2),
+        );
+        Box::new(MainScreen { con, star, arrow })
+    }
+}
+
+impl Screen for MainScreen {
+    fn input(&mut self, _app: &mut AppContext, ev: &AppEvent) -> ScreenResult {
+        match ev {
+            AppEvent::KeyDown(_) => ScreenResult::Quit,
+            AppEvent::MouseDown(_) => ScreenResult::Quit,
+            _ => ScreenResult::Continue,
+        }
+    }
+
+    fn render(&mut self, app: &mut AppContext) {
+        let time = app.time() as f32;
+        let buffer = self.con.buffer_mut();
+        buffer.fill(Some('.' as Glyph), Some(RGBA::rgb(64, 64, 64)), Some(BLACK));
+
+        // the sprites move between the cells
+        let x = 2.0 + (time * 4.0) % 36.0;
+        let star = &mut self.con.sprites_mut()[self.star];
+        star.move_to(x, 5.0);
+        star.rotation = time * 3.0;
+
+        let arrow = &mut self.con.sprites_mut()[self.arrow];
+        arrow.move_to(x, 12.0 + (time * 2.0).sin() * 3.0);
+
+        self.con.render(app);
+    }
+}
+
+fn main() {
+    let app = AppBuilder::new(1024, 768).title("Sprites Example").build();
+    app.run_screen(MainScreen::new());
+}
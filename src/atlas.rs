@@ -0,0 +1,259 @@
+use crate::font::{atlas_columns, layout, process_image, FontErr, FontInfo, FontTransparency};
+use crate::simple::Glyph;
+use crate::{Font, Image};
+use image::imageops::{self, FilterType};
+use std::collections::HashMap;
+use uni_gl::WebGLRenderingContext;
+
+/// The glyphs given to one of the tilesets in an atlas
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GlyphRange {
+    /// The glyph of the first tile
+    pub first: Glyph,
+    /// The number of tiles
+    pub count: u32,
+}
+
+impl GlyphRange {
+    /// The glyph for the tile at the index in the tileset (if it is in the range)
+    pub fn glyph(&self, index: u32) -> Option<Glyph> {
+        match index < self.count {
+            true => Some(self.first + index),
+            false => None,
+        }
+    }
+
+    /// Returns true if the glyph is one of the tiles in the range
+    pub fn contains(&self, glyph: Glyph) -> bool {
+        glyph >= self.first && glyph - self.first < self.count
+    }
+}
+
+/// The names of the tilesets in an atlas and their glyphs, see [`AtlasBuilder`]
+#[derive(Debug, Clone, Default)]
+pub struct AtlasTable {
+    ranges: HashMap<String, GlyphRange>,
+}
+
+impl AtlasTable {
+    /// The glyphs of the named tileset
+    pub fn range(&self, name: &str) -> Option<GlyphRange> {
+        self.ranges.get(name).copied()
+    }
+
+    /// The glyph for the tile at the index in the named tileset
+    pub fn glyph(&self, name: &str, index: u32) -> Option<Glyph> {
+        self.range(name).and_then(|range| range.glyph(index))
+    }
+
+    /// Finds the tileset name and tile index of a glyph
+    pub fn find(&self, glyph: Glyph) -> Option<(&str, u32)> {
+        self.ranges
+            .iter()
+            .find(|(_, range)| range.contains(glyph))
+            .map(|(name, range)| (name.as_str(), glyph - range.first))
+    }
+
+    /// The names of the tilesets
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.ranges.keys().map(|name| name.as_str())
+    }
+}
+
+/// Packs the tiles of several tileset images into one font, so one [`crate::Console`] can draw them all
+///
+/// Each tileset gets the next range of glyphs, and tiles that are not the atlas cell size are scaled
+/// to fit (nearest neighbor, for pixel art).  The transparency of each tileset is applied before it is packed.
+///
+/// ```no_run
+/// # use conapp::*;
+/// # fn example(app: &mut AppContext, monsters: &Image, items: &Image) -> Result<(), FontErr> {
+/// let mut atlas = AtlasBuilder::new(16, 16).full_color(true);
+/// atlas.add("monsters", monsters, &FontInfo::new(16, 16))?;
+/// atlas.add("items", items, &FontInfo::new(8, 8))?;
+/// let (font, table) = atlas.build(app.gl())?;
+/// app.insert_font("TILES", std::rc::Rc::new(font));
+/// let goblin = table.glyph("monsters", 3);
+/// # Ok(())
+/// # }
+/// ```
+pub struct AtlasBuilder {
+    char_size: (u32, u32),
+    columns: Option<u32>,
+    full_color: bool,
+    tiles: Vec<image::RgbaImage>,
+    table: AtlasTable,
+}
+
+impl AtlasBuilder {
+    /// Constructs an empty atlas with the given cell size
+    pub fn new(char_width: u32, char_height: u32) -> Self {
+        AtlasBuilder {
+            char_size: (char_width.max(1), char_height.max(1)),
+            columns: None,
+            full_color: false,
+            tiles: Vec::new(),
+            table: AtlasTable::default(),
+        }
+    }
+
+    /// Sets the number of tiles in each row of the atlas, default=enough for an atlas that is about square
+    pub fn columns(mut self, columns: u32) -> Self {
+        self.columns = Some(columns.max(1));
+        self
+    }
+
+    /// Sets whether the tiles keep their own colors (true) or are tinted with the fg color (false, default)
+    pub fn full_color(mut self, full_color: bool) -> Self {
+        self.full_color = full_color;
+        self
+    }
+
+    /// Adds the tiles of a tileset image, laid out as the info describes (cell size, columns, count, transparency)
+    ///
+    /// Returns the glyphs for the tiles.  Adding a name again replaces the name's range, but not its glyphs.
+    pub fn add(
+        &mut self,
+        name: &str,
+        image: &Image,
+        info: &FontInfo,
+    ) -> Result<GlyphRange, FontErr> {
+        let mut img = image.img.clone();
+        let (char_width, char_height, columns, count) = layout(info, img.width(), img.height())?;
+        if self.tiles.len() as u32 + count > crate::font::MAX_GLYPHS {
+            return Err(FontErr::BadLayout(format!(
+                "more than {} glyphs",
+                crate::font::MAX_GLYPHS
+            )));
+        }
+        process_image(&mut img, info.transparency);
+
+        let range = GlyphRange {
+            first: self.tiles.len() as Glyph,
+            count,
+        };
+        for index in 0..count {
+            let x = (index % columns) * char_width;
+            let y = (index / columns) * char_height;
+            let tile = imageops::crop_imm(&img, x, y, char_width, char_height).to_image();
+            let tile = match (char_width, char_height) == self.char_size {
+                true => tile,
+                false => imageops::resize(
+                    &tile,
+                    self.char_size.0,
+                    self.char_size.1,
+                    FilterType::Nearest,
+                ),
+            };
+            self.tiles.push(tile);
+        }
+        self.table.ranges.insert(name.to_owned(), range);
+        Ok(range)
+    }
+
+    /// The number of tiles in the atlas
+    pub fn count(&self) -> u32 {
+        self.tiles.len() as u32
+    }
+
+    /// The names of the tilesets and their glyphs
+    pub fn table(&self) -> &AtlasTable {
+        &self.table
+    }
+
+    /// The layout of the atlas image, see [`AtlasBuilder::to_image`]
+    pub fn info(&self) -> FontInfo {
+        FontInfo {
+            char_size: Some(self.char_size),
+            count: Some(self.count().max(1)),
+            columns: Some(self.atlas_columns()),
+            transparency: FontTransparency::Alpha,
+            full_color: self.full_color,
+            ..FontInfo::default()
+        }
+    }
+
+    /// Draws the tiles into the atlas image
+    pub fn to_image(&self) -> Image {
+        let (width, height) = self.char_size;
+        let columns = self.atlas_columns();
+        let rows = self.count().div_ceil(columns).max(1);
+        let mut image = Image::empty(columns * width, rows * height);
+        for (index, tile) in self.tiles.iter().enumerate() {
+            let x = (index as u32 % columns) * width;
+            let y = (index as u32 / columns) * height;
+            imageops::replace(&mut image.img, tile, x as i64, y as i64);
+        }
+        image
+    }
+
+    /// Makes the font and returns it with the table of tileset names and glyphs
+    pub fn build(self, gl: &WebGLRenderingContext) -> Result<(Font, AtlasTable), FontErr> {
+        let font = Font::from_image(gl, self.to_image(), &self.info())?;
+        Ok((font, self.table))
+    }
+
+    fn atlas_columns(&self) -> u32 {
+        self.columns
+            .unwrap_or_else(|| atlas_columns(self.count(), self.char_size))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RGBA;
+
+    // a tileset of square tiles in a row, each filled with its color
+    fn tileset(size: u32, colors: &[RGBA]) -> Image {
+        let mut image = Image::empty(size * colors.len() as u32, size);
+        for (i, color) in colors.iter().enumerate() {
+            for y in 0..size {
+                for x in 0..size {
+                    image.put_pixel(i as u32 * size + x, y, *color);
+                }
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn ranges() {
+        let red = RGBA::rgb(255, 0, 0);
+        let green = RGBA::rgb(0, 255, 0);
+        let blue = RGBA::rgb(0, 0, 255);
+        let info = |size| FontInfo {
+            transparency: FontTransparency::None,
+            ..FontInfo::new(size, size)
+        };
+
+        let mut atlas = AtlasBuilder::new(4, 4).columns(2);
+        let monsters = atlas
+            .add("monsters", &tileset(4, &[red, green, blue]), &info(4))
+            .unwrap();
+        assert_eq!(monsters, GlyphRange { first: 0, count: 3 });
+        let items = atlas
+            .add("items", &tileset(2, &[blue, green]), &info(2))
+            .unwrap();
+        assert_eq!(items, GlyphRange { first: 3, count: 2 });
+        assert_eq!(atlas.count(), 5);
+
+        let table = atlas.table();
+        assert_eq!(table.glyph("items", 1), Some(4));
+        assert_eq!(table.glyph("items", 2), None);
+        assert_eq!(table.glyph("weapons", 0), None);
+        assert_eq!(table.find(2), Some(("monsters", 2)));
+        assert_eq!(table.find(5), None);
+
+        // 5 tiles of 4x4 in 2 columns, the small tiles are scaled up
+        let image = atlas.to_image();
+        assert_eq!(image.size(), (8, 12));
+        assert_eq!(image.pixel(5, 1), Some(green));
+        assert_eq!(image.pixel(7, 7), Some(blue));
+        assert_eq!(image.pixel(3, 11), Some(green));
+        assert_eq!(image.pixel(4, 8), Some(RGBA::new()));
+
+        assert!(atlas.add("bad", &tileset(2, &[red]), &info(3)).is_err());
+        assert_eq!(atlas.count(), 5);
+    }
+}
@@ -0,0 +1,492 @@
+use crate::font::{atlas_columns, FontErr, FontInfo, FontTransparency, MAX_GLYPHS};
+use crate::simple::Glyph;
+use crate::{Codepage, Image, RGBA};
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+
+/// The glyphs of a BDF or PSF (PSF1/PSF2) bitmap font, e.g. Terminus, Unifont or a Linux console font
+///
+/// Use [`crate::Font::from_bitmap`] (or [`crate::AppContext::load_font`] with a .bdf or .psf file) to make a font
+/// from it.  The glyphs are drawn into an atlas in file order, and the codepoint of each glyph goes into
+/// the codepage, so printers can use the font directly.  Compressed (.gz) fonts are not supported.
+#[derive(Debug, Clone)]
+pub struct BitmapFont {
+    char_size: (u32, u32),
+    baseline: u32,
+    // the pixels of each glyph, row by row
+    glyphs: Vec<Vec<bool>>,
+    codepage: Option<Codepage>,
+}
+
+impl BitmapFont {
+    /// Parses a PSF1, PSF2 or BDF font (found by looking at the start of the file)
+    pub fn parse(bytes: &[u8]) -> Result<Self, FontErr> {
+        if bytes.starts_with(&PSF1_MAGIC) || bytes.starts_with(&PSF2_MAGIC) {
+            BitmapFont::parse_psf(bytes)
+        } else {
+            BitmapFont::parse_bdf(bytes)
+        }
+    }
+
+    /// Parses a BDF (Glyph Bitmap Distribution Format) font
+    ///
+    /// The cell size is the FONTBOUNDINGBOX and glyphs with an ENCODING of -1 are in the atlas, but not the codepage.
+    pub fn parse_bdf(bytes: &[u8]) -> Result<Self, FontErr> {
+        let text = std::str::from_utf8(bytes).map_err(|_| bad_file("BDF font is not text"))?;
+        let mut lines = text.lines().map(str::trim);
+        if !lines.next().unwrap_or("").starts_with("STARTFONT") {
+            return Err(bad_file("missing STARTFONT"));
+        }
+
+        // font bounding box - width, height, x offset, y offset
+        let mut bounds: Option<[i32; 4]> = None;
+        let mut default_char = None;
+        let mut glyphs = Vec::new();
+        let mut codepage = Codepage::new("BDF");
+        let mut encoding = -1;
+        let mut bbx = [0; 4];
+
+        while let Some(line) = lines.next() {
+            let (key, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "FONTBOUNDINGBOX" => bounds = Some(numbers(rest)?),
+                "DEFAULT_CHAR" => default_char = rest.trim().parse::<u32>().ok(),
+                "STARTCHAR" => {
+                    encoding = -1;
+                    bbx = [0; 4];
+                }
+                "ENCODING" => {
+                    encoding = rest
+                        .split_whitespace()
+                        .next()
+                        .and_then(|v| v.parse::<i64>().ok())
+                        .ok_or_else(|| bad_file(line))?;
+                }
+                "BBX" => bbx = numbers(rest)?,
+                "BITMAP" => {
+                    let [width, height, x_off, y_off] =
+                        bounds.ok_or_else(|| bad_file("missing FONTBOUNDINGBOX"))?;
+                    let mut pixels = vec![false; (width * height).max(0) as usize];
+                    // the top left of the glyph box in the cell (y goes up in BDF)
+                    let left = bbx[2] - x_off;
+                    let top = (y_off + height) - (bbx[3] + bbx[1]);
+                    for row in 0..bbx[1] {
+                        let hex = lines
+                            .next()
+                            .ok_or_else(|| bad_file("missing BITMAP rows"))?;
+                        let bits = hex_bits(hex).ok_or_else(|| bad_file(hex))?;
+                        for col in 0..bbx[0] {
+                            let (x, y) = (left + col, top + row);
+                            if bits.get(col as usize) == Some(&true)
+                                && (0..width).contains(&x)
+                                && (0..height).contains(&y)
+                            {
+                                pixels[(y * width + x) as usize] = true;
+                            }
+                        }
+                    }
+                    if let Some(ch) = u32::try_from(encoding).ok().and_then(char::from_u32) {
+                        codepage.insert(ch, glyphs.len() as Glyph);
+                    }
+                    glyphs.push(pixels);
+                }
+                "ENDFONT" => break,
+                _ => {}
+            }
+        }
+
+        let [width, height, _, y_off] =
+            bounds.ok_or_else(|| bad_file("missing FONTBOUNDINGBOX"))?;
+        if width <= 0 || height <= 0 {
+            return Err(bad_file("bad FONTBOUNDINGBOX"));
+        }
+        if let Some(glyph) = default_char
+            .and_then(char::from_u32)
+            .filter(|ch| codepage.has_char(*ch))
+            .map(|ch| codepage.to_glyph(ch))
+        {
+            codepage = codepage.with_missing(glyph);
+        }
+        BitmapFont::new(
+            (width as u32, height as u32),
+            (height + y_off - 1).clamp(0, height - 1) as u32,
+            glyphs,
+            Some(codepage),
+        )
+    }
+
+    /// Parses a PSF1 or PSF2 (Linux console) font
+    ///
+    /// Fonts without a unicode table have no codepage, so glyphs are used as (ch as u32).
+    pub fn parse_psf(bytes: &[u8]) -> Result<Self, FontErr> {
+        let PsfHeader {
+            width,
+            height,
+            count,
+            bytes_per_glyph,
+            start,
+            has_table,
+            psf2,
+        } = PsfHeader::read(bytes)?;
+
+        let row_bytes = width.div_ceil(8) as usize;
+        if width == 0 || height == 0 || bytes_per_glyph < row_bytes * height as usize {
+            return Err(bad_file("bad PSF glyph size"));
+        }
+        let end = count
+            .checked_mul(bytes_per_glyph)
+            .and_then(|len| len.checked_add(start))
+            .filter(|end| *end <= bytes.len())
+            .ok_or_else(|| bad_file("PSF font is too short"))?;
+
+        let glyphs = bytes[start..end]
+            .chunks(bytes_per_glyph)
+            .map(|data| {
+                let mut pixels = Vec::with_capacity((width * height) as usize);
+                for row in data.chunks(row_bytes).take(height as usize) {
+                    pixels.extend((0..width as usize).map(|x| row[x / 8] & (0x80 >> (x % 8)) != 0));
+                }
+                pixels
+            })
+            .collect();
+
+        let codepage = match has_table {
+            false => None,
+            true if psf2 => Some(psf2_table(&bytes[end..], count)),
+            true => Some(psf1_table(&bytes[end..], count)),
+        };
+        BitmapFont::new((width, height), height - 1, glyphs, codepage)
+    }
+
+    fn new(
+        char_size: (u32, u32),
+        baseline: u32,
+        glyphs: Vec<Vec<bool>>,
+        codepage: Option<Codepage>,
+    ) -> Result<Self, FontErr> {
+        if glyphs.is_empty() {
+            return Err(bad_file("no glyphs"));
+        }
+        if glyphs.len() > MAX_GLYPHS as usize {
+            return Err(bad_file(&format!("more than {} glyphs", MAX_GLYPHS)));
+        }
+        Ok(BitmapFont {
+            char_size,
+            baseline,
+            glyphs,
+            codepage,
+        })
+    }
+
+    /// The size of a cell in pixels
+    pub fn char_size(&self) -> (u32, u32) {
+        self.char_size
+    }
+
+    /// The number of glyphs
+    pub fn count(&self) -> u32 {
+        self.glyphs.len() as u32
+    }
+
+    /// The row of pixels (from the top of the cell) that the glyphs sit on
+    pub fn baseline(&self) -> u32 {
+        self.baseline
+    }
+
+    /// The mapping of codepoints to glyphs (if the font has one)
+    pub fn codepage(&self) -> Option<&Codepage> {
+        self.codepage.as_ref()
+    }
+
+    /// Returns true if the pixel (x, y) of the glyph is set
+    pub fn pixel(&self, glyph: Glyph, x: u32, y: u32) -> bool {
+        if x >= self.char_size.0 || y >= self.char_size.1 {
+            return false;
+        }
+        let index = (y * self.char_size.0 + x) as usize;
+        self.glyphs
+            .get(glyph as usize)
+            .is_some_and(|pixels| pixels[index])
+    }
+
+    /// The number of glyphs in each row of the atlas (16 or more, so the atlas is about square)
+    pub fn columns(&self) -> u32 {
+        atlas_columns(self.count(), self.char_size)
+    }
+
+    /// The layout of the atlas, see [`BitmapFont::to_image`]
+    pub fn info(&self) -> FontInfo {
+        FontInfo {
+            char_size: Some(self.char_size),
+            count: Some(self.count()),
+            columns: Some(self.columns()),
+            transparency: FontTransparency::Alpha,
+            ..FontInfo::default()
+        }
+    }
+
+    /// Draws the glyphs into an atlas image - white where the pixels are set and transparent elsewhere
+    pub fn to_image(&self) -> Image {
+        let (width, height) = self.char_size;
+        let columns = self.columns();
+        let rows = self.count().div_ceil(columns);
+        let mut image = Image::empty(columns * width, rows * height);
+        for (index, pixels) in self.glyphs.iter().enumerate() {
+            let left = (index as u32 % columns) * width;
+            let top = (index as u32 / columns) * height;
+            for (i, _) in pixels.iter().enumerate().filter(|(_, set)| **set) {
+                let (x, y) = (i as u32 % width, i as u32 / width);
+                image.put_pixel(left + x, top + y, RGBA::rgba(255, 255, 255, 255));
+            }
+        }
+        image
+    }
+}
+
+struct PsfHeader {
+    width: u32,
+    height: u32,
+    count: usize,
+    bytes_per_glyph: usize,
+    // where the glyphs start
+    start: usize,
+    has_table: bool,
+    psf2: bool,
+}
+
+impl PsfHeader {
+    fn read(bytes: &[u8]) -> Result<Self, FontErr> {
+        if bytes.starts_with(&PSF2_MAGIC) {
+            let field = |i: usize| -> Result<u32, FontErr> {
+                let b = bytes
+                    .get(i * 4..i * 4 + 4)
+                    .ok_or_else(|| bad_file("short PSF2 header"))?;
+                Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            };
+            // magic, version, header size, flags, length, glyph size, height, width
+            Ok(PsfHeader {
+                width: field(7)?,
+                height: field(6)?,
+                count: field(4)? as usize,
+                bytes_per_glyph: field(5)? as usize,
+                start: field(2)? as usize,
+                has_table: field(3)? & 1 == 1,
+                psf2: true,
+            })
+        } else if bytes.starts_with(&PSF1_MAGIC) && bytes.len() >= 4 {
+            // magic, mode, height
+            let mode = bytes[2];
+            let height = bytes[3] as u32;
+            Ok(PsfHeader {
+                width: 8,
+                height,
+                count: if mode & 0x01 == 0x01 { 512 } else { 256 },
+                bytes_per_glyph: height as usize,
+                start: 4,
+                has_table: mode & 0x06 != 0,
+                psf2: false,
+            })
+        } else {
+            Err(bad_file("not a PSF font"))
+        }
+    }
+}
+
+fn bad_file(msg: &str) -> FontErr {
+    FontErr::BadFontFile(msg.to_owned())
+}
+
+// the numbers in a BDF line (e.g. "8 16 0 -2")
+fn numbers(text: &str) -> Result<[i32; 4], FontErr> {
+    let mut out = [0; 4];
+    let mut values = text.split_whitespace().map(|v| v.parse::<i32>());
+    for item in out.iter_mut() {
+        *item = match values.next() {
+            Some(Ok(value)) => value,
+            _ => return Err(bad_file(text)),
+        };
+    }
+    Ok(out)
+}
+
+// the bits of a row of BDF hex (e.g. "3C" = 00111100)
+fn hex_bits(hex: &str) -> Option<Vec<bool>> {
+    let mut bits = Vec::with_capacity(hex.len() * 4);
+    for ch in hex.chars() {
+        let value = ch.to_digit(16)?;
+        bits.extend((0..4).rev().map(|bit| value & (1 << bit) != 0));
+    }
+    Some(bits)
+}
+
+// PSF1 unicode table - u16 codepoints for each glyph, ending with 0xFFFF (0xFFFE starts sequences)
+fn psf1_table(table: &[u8], count: usize) -> Codepage {
+    let mut codepage = Codepage::new("PSF");
+    let mut glyph = 0;
+    let mut in_sequence = false;
+    for value in table
+        .chunks_exact(2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    {
+        match value {
+            0xFFFF => {
+                glyph += 1;
+                in_sequence = false;
+            }
+            0xFFFE => in_sequence = true,
+            _ if in_sequence || glyph >= count => {}
+            _ => {
+                if let Some(ch) = char::from_u32(value as u32) {
+                    codepage.insert(ch, glyph as Glyph);
+                }
+            }
+        }
+    }
+    codepage
+}
+
+// PSF2 unicode table - utf-8 codepoints for each glyph, ending with 0xFF (0xFE starts sequences)
+fn psf2_table(table: &[u8], count: usize) -> Codepage {
+    let mut codepage = Codepage::new("PSF");
+    for (glyph, entry) in table.split(|b| *b == 0xFF).take(count).enumerate() {
+        let singles = entry.split(|b| *b == 0xFE).next().unwrap_or(&[]);
+        for ch in String::from_utf8_lossy(singles).chars() {
+            if ch != char::REPLACEMENT_CHARACTER {
+                codepage.insert(ch, glyph as Glyph);
+            }
+        }
+    }
+    codepage
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BDF: &str = "STARTFONT 2.1
+FONT -test-
+FONTBOUNDINGBOX 4 6 0 -2
+STARTPROPERTIES 1
+DEFAULT_CHAR 63
+ENDPROPERTIES
+CHARS 3
+STARTCHAR A
+ENCODING 65
+DWIDTH 4 0
+BBX 3 4 0 0
+BITMAP
+40
+A0
+E0
+A0
+ENDCHAR
+STARTCHAR question
+ENCODING 63
+BBX 2 1 1 -2
+BITMAP
+C0
+ENDCHAR
+STARTCHAR unencoded
+ENCODING -1
+BBX 1 1 0 0
+BITMAP
+80
+ENDCHAR
+ENDFONT
+";
+
+    fn glyph_rows(font: &BitmapFont, glyph: Glyph) -> Vec<String> {
+        let (width, height) = font.char_size();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| if font.pixel(glyph, x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bdf() {
+        let font = BitmapFont::parse(BDF.as_bytes()).unwrap();
+        assert_eq!(font.char_size(), (4, 6));
+        assert_eq!(font.count(), 3);
+        assert_eq!(font.baseline(), 3);
+        assert_eq!(
+            glyph_rows(&font, 0),
+            [".#..", "#.#.", "###.", "#.#.", "....", "...."]
+        );
+        assert_eq!(
+            glyph_rows(&font, 1),
+            ["....", "....", "....", "....", "....", ".##."]
+        );
+
+        let codepage = font.codepage().unwrap();
+        assert_eq!(codepage.to_glyph('A'), 0);
+        assert_eq!(codepage.to_glyph('?'), 1);
+        assert_eq!(codepage.to_glyph('Z'), 1);
+        assert_eq!(codepage.len(), 2);
+
+        assert!(BitmapFont::parse(b"STARTFONT 2.1\nENDFONT").is_err());
+        assert!(BitmapFont::parse(b"taco").is_err());
+    }
+
+    #[test]
+    fn psf() {
+        // PSF1 - 256 glyphs of 8x2 with a unicode table
+        let mut bytes = vec![0x36, 0x04, 0x02, 2];
+        for i in 0..256 {
+            bytes.extend([i as u8, 0x81]);
+        }
+        for i in 0..256u16 {
+            bytes.extend(i.to_le_bytes());
+            if i == 1 {
+                bytes.extend(0x263Au16.to_le_bytes());
+                bytes.extend([0xFE, 0xFF, 0x41, 0x00, 0x41, 0x03]);
+            }
+            bytes.extend([0xFF, 0xFF]);
+        }
+        let font = BitmapFont::parse(&bytes).unwrap();
+        assert_eq!(font.char_size(), (8, 2));
+        assert_eq!(font.count(), 256);
+        assert_eq!(glyph_rows(&font, 3), ["......##", "#......#"]);
+        let codepage = font.codepage().unwrap();
+        assert_eq!(codepage.to_glyph('☺'), 1);
+        assert_eq!(codepage.to_glyph('A'), 65);
+
+        // PSF2 - 2 glyphs of 10x1 with a unicode table
+        let mut bytes = PSF2_MAGIC.to_vec();
+        for value in [0u32, 32, 1, 2, 2, 1, 10] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend([0xFF, 0xC0, 0x00, 0x40]);
+        bytes.extend("é".as_bytes());
+        bytes.extend([0xFF]);
+        bytes.extend("–—".as_bytes());
+        bytes.extend([0xFE, b'x', 0xFF]);
+        let font = BitmapFont::parse(&bytes).unwrap();
+        assert_eq!(font.char_size(), (10, 1));
+        assert_eq!(glyph_rows(&font, 0), ["##########"]);
+        assert_eq!(glyph_rows(&font, 1), [".........#"]);
+        let codepage = font.codepage().unwrap();
+        assert_eq!(codepage.to_glyph('é'), 0);
+        assert_eq!(codepage.to_glyph('—'), 1);
+        assert!(!codepage.has_char('x'));
+
+        bytes.truncate(34);
+        assert!(BitmapFont::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn atlas() {
+        let font = BitmapFont::parse(BDF.as_bytes()).unwrap();
+        assert_eq!(font.columns(), 16);
+        let image = font.to_image();
+        assert_eq!(image.size(), (64, 6));
+        assert_eq!(image.pixel(1, 0), Some(RGBA::rgba(255, 255, 255, 255)));
+        assert_eq!(image.pixel(0, 0), Some(RGBA::rgba(0, 0, 0, 0)));
+        assert_eq!(image.pixel(5, 5), Some(RGBA::rgba(255, 255, 255, 255)));
+        assert_eq!(font.info().count, Some(3));
+    }
+}
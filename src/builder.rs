@@ -9,8 +9,8 @@ pub type StartupFn = dyn Fn(&mut AppContext) -> ();
 pub struct AppBuilder {
     /// window configuration info
     pub(crate) config: AppConfig,
-    /// fonts to load
-    pub(crate) fonts: Vec<String>,
+    /// fonts to load - (font path, descriptor path)
+    pub(crate) fonts: Vec<(String, Option<String>)>,
     /// images to load
     pub(crate) images: Vec<String>,
     /// files to load
@@ -90,14 +90,21 @@ impl AppBuilder {
 
     /// Loads a font on startup
     pub fn font(mut self, font_path: &str) -> Self {
-        self.fonts.push(font_path.to_owned());
+        self.fonts.push((font_path.to_owned(), None));
+        self
+    }
+
+    /// Loads a font with a sidecar descriptor on startup (see [`crate::FontInfo::parse`])
+    pub fn font_with_info(mut self, font_path: &str, info_path: &str) -> Self {
+        self.fonts
+            .push((font_path.to_owned(), Some(info_path.to_owned())));
         self
     }
 
     /// Loads a list of fonts on startup
     pub fn fonts(mut self, font_paths: &[&str]) -> Self {
         for font_path in font_paths {
-            self.fonts.push((*font_path).to_owned());
+            self.fonts.push(((*font_path).to_owned(), None));
         }
         self
     }
@@ -0,0 +1,353 @@
+use crate::simple::{parse_glyph_code, Glyph};
+use std::collections::HashMap;
+
+/// A mapping between chars and the glyphs (atlas indexes) of a font
+///
+/// Attach one to a [`crate::Font`] (see [`crate::AppContext::set_font_codepage`]) and the
+/// consoles that use the font pass it to their buffer, where the printers pick it up.
+#[derive(Debug, Clone)]
+pub struct Codepage {
+    name: String,
+    to_glyph: HashMap<char, Glyph>,
+    from_glyph: Vec<char>,
+    missing: Glyph,
+}
+
+impl Codepage {
+    /// Constructs an empty codepage
+    pub fn new(name: &str) -> Self {
+        Codepage {
+            name: name.to_owned(),
+            to_glyph: HashMap::new(),
+            from_glyph: Vec::new(),
+            missing: 0,
+        }
+    }
+
+    /// Constructs a codepage where each char is the glyph at its index, '\0' skips a glyph
+    pub fn from_chars(name: &str, chars: &str) -> Self {
+        let mut codepage = Codepage::new(name);
+        codepage.insert_chars(0, chars);
+        codepage
+    }
+
+    /// IBM PC (DOS) US - the layout of most roguelike fonts
+    pub fn cp437() -> Self {
+        Codepage::from_chars("CP437", CP437)
+    }
+
+    /// DOS Western Europe
+    pub fn cp850() -> Self {
+        Codepage::from_chars("CP850", CP850)
+    }
+
+    /// DOS Cyrillic
+    pub fn cp866() -> Self {
+        Codepage::from_chars("CP866", CP866)
+    }
+
+    /// ISO 8859-1
+    pub fn latin1() -> Self {
+        Codepage::from_chars("Latin-1", LATIN1)
+    }
+
+    /// Sinclair ZX Spectrum - ASCII with £ and ©, and the block graphics at 128-143
+    pub fn zx_spectrum() -> Self {
+        Codepage::from_chars("ZX Spectrum", ZX_SPECTRUM)
+    }
+
+    /// Atari 8-bit (ATASCII) - line and block graphics at 0-31
+    pub fn atascii() -> Self {
+        Codepage::from_chars("ATASCII", ATASCII)
+    }
+
+    /// Finds a built in codepage by name (ignoring case, '-', '_' and ' '), e.g. "cp437" or "zx_spectrum"
+    pub fn builtin(name: &str) -> Option<Self> {
+        let codepage = match name.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "cp437" => Codepage::cp437(),
+            "cp850" => Codepage::cp850(),
+            "cp866" => Codepage::cp866(),
+            "latin1" | "iso88591" => Codepage::latin1(),
+            "zxspectrum" => Codepage::zx_spectrum(),
+            "atascii" => Codepage::atascii(),
+            _ => return None,
+        };
+        Some(codepage)
+    }
+
+    /// Parses a codepage from text
+    ///
+    /// Each line is an index (decimal or 0x hex), a ':', and the chars for the glyphs
+    /// starting at that index.  The chars start after the ": " and run to the end of the line
+    /// (so they can include spaces).  Blank lines and lines starting with '#' are skipped.
+    ///
+    /// ```text
+    /// # box drawing
+    /// 0xB3: │┤╡╢╖╕╣║╗╝╜╛┐
+    /// 32:  !"#$%&'()*+,-./
+    /// ```
+    pub fn parse(name: &str, text: &str) -> Result<Self, CodepageErr> {
+        let mut codepage = Codepage::new(name);
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |kind| CodepageErr { kind, line: i };
+            let (index, chars) = line
+                .split_once(':')
+                .ok_or_else(|| err(CodepageErrKind::MissingColon))?;
+            let index =
+                parse_glyph_code(index.trim()).ok_or_else(|| err(CodepageErrKind::BadIndex))?;
+            let chars = chars.strip_prefix(' ').unwrap_or(chars);
+            codepage.insert_chars(index, chars);
+        }
+        Ok(codepage)
+    }
+
+    /// Sets the glyph used for chars that are not in the codepage, default=0
+    pub fn with_missing(mut self, glyph: Glyph) -> Self {
+        self.missing = glyph;
+        self
+    }
+
+    /// The name of the codepage
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Maps the char to the glyph, replacing any previous mapping of the glyph and char
+    pub fn insert(&mut self, ch: char, glyph: Glyph) {
+        let index = glyph as usize;
+        if self.from_glyph.len() <= index {
+            self.from_glyph.resize(index + 1, '\0');
+        }
+        let old = self.from_glyph[index];
+        if old != '\0' && self.to_glyph.get(&old) == Some(&glyph) {
+            self.to_glyph.remove(&old);
+        }
+        self.from_glyph[index] = ch;
+        // the first glyph for a char wins (e.g. ' ' is 32 even if another glyph looks like a space)
+        self.to_glyph.entry(ch).or_insert(glyph);
+    }
+
+    fn insert_chars(&mut self, start: Glyph, chars: &str) {
+        for (i, ch) in chars.chars().enumerate() {
+            if ch != '\0' || start as usize + i == 0 {
+                self.insert(ch, start + i as Glyph);
+            }
+        }
+    }
+
+    /// The number of glyphs in the codepage (the highest glyph + 1)
+    pub fn len(&self) -> usize {
+        self.from_glyph.len()
+    }
+
+    /// Returns true if there are no glyphs in the codepage
+    pub fn is_empty(&self) -> bool {
+        self.from_glyph.is_empty()
+    }
+
+    /// Converts a char to its glyph, or the missing glyph if it is not in the codepage
+    pub fn to_glyph(&self, ch: char) -> Glyph {
+        self.to_glyph.get(&ch).copied().unwrap_or(self.missing)
+    }
+
+    /// Returns true if the codepage has a glyph for the char
+    pub fn has_char(&self, ch: char) -> bool {
+        self.to_glyph.contains_key(&ch)
+    }
+
+    /// Converts a glyph back to its char, ' ' if it is not in the codepage
+    pub fn from_glyph(&self, glyph: Glyph) -> char {
+        match self.from_glyph.get(glyph as usize) {
+            None | Some('\0') => ' ',
+            Some(ch) => *ch,
+        }
+    }
+
+    /// Converts a string into glyphs
+    pub fn string_to_glyphs(&self, text: &str) -> Vec<Glyph> {
+        text.chars().map(|ch| self.to_glyph(ch)).collect()
+    }
+}
+
+/// The kinds of errors when parsing a codepage
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CodepageErrKind {
+    /// The line does not have a ':' after the index
+    MissingColon,
+    /// The index is not a number
+    BadIndex,
+}
+
+/// An error found by [`Codepage::parse`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CodepageErr {
+    /// What went wrong
+    pub kind: CodepageErrKind,
+    /// The index of the line with the error
+    pub line: usize,
+}
+
+impl std::fmt::Display for CodepageErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self.kind {
+            CodepageErrKind::MissingColon => "missing ':' after the index",
+            CodepageErrKind::BadIndex => "index is not a number",
+        };
+        write!(f, "{} at line {}", msg, self.line)
+    }
+}
+
+impl std::error::Error for CodepageErr {}
+
+// IBM PC (DOS) US
+const CP437: &str = concat!(
+    "\0☺☻♥♦♣♠•◘○◙♂♀♪♫☼",
+    "►◄↕‼¶§▬↨↑↓→←∟↔▲▼",
+    " !\"#$%&'()*+,-./",
+    "0123456789:;<=>?",
+    "@ABCDEFGHIJKLMNO",
+    "PQRSTUVWXYZ[\\]^_",
+    "`abcdefghijklmno",
+    "pqrstuvwxyz{|}~⌂",
+    "ÇüéâäàåçêëèïîìÄÅ",
+    "ÉæÆôöòûùÿÖÜ¢£¥₧ƒ",
+    "áíóúñÑªº¿⌐¬½¼¡«»",
+    "░▒▓│┤╡╢╖╕╣║╗╝╜╛┐",
+    "└┴┬├─┼╞╟╚╔╩╦╠═╬╧",
+    "╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀",
+    "αßΓπΣσµτΦΘΩδ∞φε∩",
+    "≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{A0}",
+);
+// DOS Western Europe
+const CP850: &str = concat!(
+    "\0☺☻♥♦♣♠•◘○◙♂♀♪♫☼",
+    "►◄↕‼¶§▬↨↑↓→←∟↔▲▼",
+    " !\"#$%&'()*+,-./",
+    "0123456789:;<=>?",
+    "@ABCDEFGHIJKLMNO",
+    "PQRSTUVWXYZ[\\]^_",
+    "`abcdefghijklmno",
+    "pqrstuvwxyz{|}~⌂",
+    "ÇüéâäàåçêëèïîìÄÅ",
+    "ÉæÆôöòûùÿÖÜø£Ø×ƒ",
+    "áíóúñÑªº¿®¬½¼¡«»",
+    "░▒▓│┤ÁÂÀ©╣║╗╝¢¥┐",
+    "└┴┬├─┼ãÃ╚╔╩╦╠═╬¤",
+    "ðÐÊËÈıÍÎÏ┘┌█▄¦Ì▀",
+    "ÓßÔÒõÕµþÞÚÛÙýÝ¯´",
+    "\u{AD}±‗¾¶§÷¸°¨·¹³²■\u{A0}",
+);
+// DOS Cyrillic
+const CP866: &str = concat!(
+    "\0☺☻♥♦♣♠•◘○◙♂♀♪♫☼",
+    "►◄↕‼¶§▬↨↑↓→←∟↔▲▼",
+    " !\"#$%&'()*+,-./",
+    "0123456789:;<=>?",
+    "@ABCDEFGHIJKLMNO",
+    "PQRSTUVWXYZ[\\]^_",
+    "`abcdefghijklmno",
+    "pqrstuvwxyz{|}~⌂",
+    "АБВГДЕЖЗИЙКЛМНОП",
+    "РСТУФХЦЧШЩЪЫЬЭЮЯ",
+    "абвгдежзийклмноп",
+    "░▒▓│┤╡╢╖╕╣║╗╝╜╛┐",
+    "└┴┬├─┼╞╟╚╔╩╦╠═╬╧",
+    "╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀",
+    "рстуфхцчшщъыьэюя",
+    "ЁёЄєЇїЎў°∙·√№¤■\u{A0}",
+);
+// ISO 8859-1, 0x80-0x9F are not used
+const LATIN1: &str = concat!(
+    "\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+    "\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+    " !\"#$%&'()*+,-./",
+    "0123456789:;<=>?",
+    "@ABCDEFGHIJKLMNO",
+    "PQRSTUVWXYZ[\\]^_",
+    "`abcdefghijklmno",
+    "pqrstuvwxyz{|}~\0",
+    "\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+    "\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+    "\u{A0}¡¢£¤¥¦§¨©ª«¬\u{AD}®¯",
+    "°±²³´µ¶·¸¹º»¼½¾¿",
+    "ÀÁÂÃÄÅÆÇÈÉÊËÌÍÎÏ",
+    "ÐÑÒÓÔÕÖ×ØÙÚÛÜÝÞß",
+    "àáâãäåæçèéêëìíîï",
+    "ðñòóôõö÷øùúûüýþÿ",
+);
+// Sinclair ZX Spectrum, 0x80-0x8F are the block graphics
+const ZX_SPECTRUM: &str = concat!(
+    "\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+    "\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+    " !\"#$%&'()*+,-./",
+    "0123456789:;<=>?",
+    "@ABCDEFGHIJKLMNO",
+    "PQRSTUVWXYZ[\\]↑_",
+    "£abcdefghijklmno",
+    "pqrstuvwxyz{|}~©",
+    " ▝▘▀▗▐▚▜▖▞▌▛▄▟▙█",
+);
+// Atari 8-bit
+const ATASCII: &str = concat!(
+    "♥├▕┘┤┐╱╲◢▗◣▝▘▔▁▖",
+    "♣┌─┼•▄▎┬┴▌└␛↑↓←→",
+    " !\"#$%&'()*+,-./",
+    "0123456789:;<=>?",
+    "@ABCDEFGHIJKLMNO",
+    "PQRSTUVWXYZ[\\]^_",
+    "◆abcdefghijklmno",
+    "pqrstuvwxyz♠|↰◀▶",
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builtin() {
+        let cp437 = Codepage::cp437();
+        assert_eq!(cp437.len(), 256);
+        for ch in "Hello ½Ñ░╬Ω≈☺".chars() {
+            assert_eq!(cp437.to_glyph(ch), crate::codepage437::to_glyph(ch));
+        }
+        assert_eq!(cp437.to_glyph(' '), 32);
+        assert_eq!(cp437.from_glyph(219), '█');
+
+        assert_eq!(Codepage::cp850().to_glyph('Ø'), 157);
+        assert_eq!(Codepage::cp866().to_glyph('Ж'), 134);
+        assert_eq!(Codepage::latin1().to_glyph('é'), 233);
+        assert!(!Codepage::latin1().has_char('☺'));
+        assert_eq!(Codepage::zx_spectrum().to_glyph('£'), 0x60);
+        assert_eq!(Codepage::atascii().to_glyph('♥'), 0);
+        assert_eq!(Codepage::atascii().to_glyph('♠'), 0x7B);
+
+        assert_eq!(Codepage::builtin("CP-850").unwrap().name(), "CP850");
+        assert_eq!(
+            Codepage::builtin("zx spectrum").unwrap().name(),
+            "ZX Spectrum"
+        );
+        assert!(Codepage::builtin("taco").is_none());
+    }
+
+    #[test]
+    fn parse() {
+        let text = "# comment\n0x41: ABC\n\n32:  !\n200: ☺";
+        let codepage = Codepage::parse("test", text).unwrap().with_missing(63);
+        assert_eq!(codepage.to_glyph('B'), 66);
+        assert_eq!(codepage.to_glyph(' '), 32);
+        assert_eq!(codepage.to_glyph('!'), 33);
+        assert_eq!(codepage.to_glyph('☺'), 200);
+        assert_eq!(codepage.to_glyph('z'), 63);
+        assert_eq!(codepage.from_glyph(200), '☺');
+        assert_eq!(codepage.len(), 201);
+
+        let err = Codepage::parse("bad", "65: A\nx41: B").unwrap_err();
+        assert_eq!(err.kind, CodepageErrKind::BadIndex);
+        assert_eq!(err.line, 1);
+        let err = Codepage::parse("bad", "65 A").unwrap_err();
+        assert_eq!(err.kind, CodepageErrKind::MissingColon);
+    }
+}
@@ -1,10 +1,10 @@
 use super::input::AppInput;
 use super::Font;
 use crate::app::File;
-use crate::font::parse_char_size;
-use crate::simple::Program;
+use crate::font::FontInfo;
+use crate::simple::{Program, SpriteProgram};
 use crate::{console, MsgData};
-use crate::{Image, RGBA};
+use crate::{BitmapFont, Codepage, GlyphRegistry, Image, RGBA};
 use std::collections::HashMap;
 use std::rc::Rc;
 use uni_gl::{BufferBit, WebGLRenderingContext};
@@ -39,6 +39,8 @@ impl LoadInfo {
 pub enum LoadError {
     OpenError(std::io::Error),
     ReadError(std::io::Error),
+    ParseError(String),
+    FontError(crate::FontErr),
 }
 
 pub struct AppContext {
@@ -46,13 +48,22 @@ pub struct AppContext {
     pub(crate) input: AppInput,
     pub(crate) fps: Fps,
     pub(crate) screen_size: (u32, u32),
+    // the screen size in device pixels (screen_size * hidpi factor)
+    pub(crate) real_screen_size: (u32, u32),
     pub(crate) frame_time_ms: f64,
+    // when the app started (see perf_now)
+    start_time: f64,
     pub(crate) gl: WebGLRenderingContext,
     pub(crate) fonts: HashMap<String, Rc<Font>>,
+    // codepages by font name, kept for fonts that are not loaded yet
+    pub(crate) codepages: HashMap<String, Rc<Codepage>>,
+    // glyph names by font name, kept for fonts that are not loaded yet
+    pub(crate) glyphs: HashMap<String, Rc<GlyphRegistry>>,
     pub(crate) images: HashMap<String, Rc<Image>>,
     pub(crate) ready: bool,
     // pub(crate) file_loader: FileLoader,
     pub(crate) simple_program: Program,
+    pub(crate) sprite_program: SpriteProgram,
     pub(crate) files_to_load: Vec<LoadInfo>,
     // #[cfg(feature = "ecs")]
     // pub resources: Resources,
@@ -65,6 +76,7 @@ impl AppContext {
     pub(crate) fn new(
         gl: WebGLRenderingContext,
         screen_size: (u32, u32),
+        real_screen_size: (u32, u32),
         input: AppInput,
         fps_goal: u32,
     ) -> Self {
@@ -72,10 +84,15 @@ impl AppContext {
             input,
             fps: Fps::new(fps_goal),
             screen_size: screen_size,
+            real_screen_size,
             frame_time_ms: 0.0,
+            start_time: crate::app::perf_now(),
             simple_program: Program::new(&gl),
+            sprite_program: SpriteProgram::new(&gl),
             gl,
             fonts: HashMap::new(),
+            codepages: HashMap::new(),
+            glyphs: HashMap::new(),
             images: HashMap::new(),
             ready: false,
             // file_loader: FileLoader::new(),
@@ -91,6 +108,7 @@ impl AppContext {
         let default_font = Rc::new(Font::new(&ctx.gl, TERMINAL_8X8_BYTES, (8, 8)));
         ctx.insert_font("SUBCELL", sub_cell_font);
         ctx.insert_font("DEFAULT", default_font);
+        ctx.set_font_codepage("DEFAULT", Codepage::cp437());
 
         console(format!(
             "AppContext::new - screen_size={:?}",
@@ -100,12 +118,18 @@ impl AppContext {
         ctx
     }
 
-    pub(crate) fn resize(&mut self, screen_width: u32, screen_height: u32) {
+    pub(crate) fn resize(
+        &mut self,
+        screen_width: u32,
+        screen_height: u32,
+        real_screen_size: (u32, u32),
+    ) {
         console(format!(
             "appcontext::resize - {}x{}",
             screen_width, screen_height
         ));
         self.screen_size = (screen_width, screen_height);
+        self.real_screen_size = real_screen_size;
     }
 
     pub fn has_files_to_load(&self) -> bool {
@@ -181,10 +205,20 @@ impl AppContext {
         self.frame_time_ms
     }
 
+    /// The time since the app started, in seconds - it drives the animated glyphs (see [`crate::GlyphAnimation`])
+    pub fn time(&self) -> f64 {
+        crate::app::perf_now() - self.start_time
+    }
+
     pub fn screen_size(&self) -> (u32, u32) {
         self.screen_size
     }
 
+    /// The screen size in device pixels, which is larger than [`AppContext::screen_size`] on hidpi screens
+    pub fn real_screen_size(&self) -> (u32, u32) {
+        self.real_screen_size
+    }
+
     // pub fn simple_console(&mut self, width: u32, height: u32, fontpath: &str) -> Console {
     //     Console::new(width, height, fontpath)
     // }
@@ -212,15 +246,67 @@ impl AppContext {
         }
     }
 
+    /// Loads a font whose file name ends with the cell size (e.g. 'name_8x8.png')
+    ///
+    /// BDF (.bdf) and PSF (.psf, .psfu) fonts are loaded with [`BitmapFont`] and do not need the cell size in the name.
     pub fn load_font(&mut self, font_path: &str) -> Result<(), LoadError> {
-        let char_size = parse_char_size(font_path);
+        let lower = font_path.to_lowercase();
+        if [".bdf", ".psf", ".psfu"]
+            .iter()
+            .any(|ext| lower.ends_with(ext))
+        {
+            return self.load_bitmap_font(font_path);
+        }
+        let info = FontInfo::from_path(font_path).map_err(LoadError::FontError)?;
+        self.load_font_image(font_path, info)
+    }
+
+    /// Loads a font with a sidecar descriptor that gives its layout (see [`FontInfo::parse`])
+    ///
+    /// If the descriptor does not have the cell size, it comes from the font file name.
+    pub fn load_font_with_info(
+        &mut self,
+        font_path: &str,
+        info_path: &str,
+    ) -> Result<(), LoadError> {
         let path = font_path.to_owned();
+        self.load_file(
+            info_path,
+            Box::new(move |data, app: &mut AppContext| {
+                let text = String::from_utf8_lossy(&data);
+                let mut info = FontInfo::parse(&text).map_err(LoadError::FontError)?;
+                if info.char_size.is_none() {
+                    info.char_size = FontInfo::from_path(&path)
+                        .map_err(LoadError::FontError)?
+                        .char_size;
+                }
+                app.load_font_image(&path, info)
+            }),
+        )
+    }
+
+    fn load_bitmap_font(&mut self, font_path: &str) -> Result<(), LoadError> {
+        let path = font_path.to_owned();
+        self.load_file(
+            font_path,
+            Box::new(move |data, app: &mut AppContext| {
+                let bitmap = BitmapFont::parse(&data).map_err(LoadError::FontError)?;
+                let font = Font::from_bitmap(app.gl(), &bitmap).map_err(LoadError::FontError)?;
+                app.insert_font(&path, Rc::new(font));
+                console(format!("font load complete - {}", path));
+                Ok(())
+            }),
+        )
+    }
 
+    fn load_font_image(&mut self, font_path: &str, info: FontInfo) -> Result<(), LoadError> {
+        let path = font_path.to_owned();
         self.load_file(
             font_path,
             Box::new(move |data, app: &mut AppContext| {
-                let font = Rc::new(Font::new(app.gl(), &data, char_size));
-                app.insert_font(&path, font);
+                let font =
+                    Font::from_bytes(app.gl(), &data, &info).map_err(LoadError::FontError)?;
+                app.insert_font(&path, Rc::new(font));
                 console(format!("font load complete - {}", path));
                 Ok(())
             }),
@@ -239,7 +325,77 @@ impl AppContext {
         )
     }
 
+    /// Loads a codepage text file (see [`Codepage::parse`]) and attaches it to the font
+    ///
+    /// The font does not need to be loaded yet.
+    pub fn load_codepage(&mut self, codepage_path: &str, font_name: &str) -> Result<(), LoadError> {
+        let path = codepage_path.to_owned();
+        let font_name = font_name.to_owned();
+        self.load_file(
+            codepage_path,
+            Box::new(move |data, app: &mut AppContext| {
+                let text = String::from_utf8_lossy(&data);
+                let codepage = Codepage::parse(&path, &text)
+                    .map_err(|e| LoadError::ParseError(format!("{} - {}", path, e)))?;
+                app.set_font_codepage(&font_name, codepage);
+                console(format!("codepage load complete - {}", path));
+                Ok(())
+            }),
+        )
+    }
+
+    /// Attaches the codepage to the font, so printers use it for consoles with that font
+    ///
+    /// The font does not need to be loaded yet.
+    pub fn set_font_codepage(&mut self, font_name: &str, codepage: Codepage) {
+        let codepage = Rc::new(codepage);
+        if let Some(font) = self.fonts.get(font_name) {
+            font.set_codepage(Some(codepage.clone()));
+        }
+        self.codepages.insert(font_name.to_owned(), codepage);
+    }
+
+    /// Loads a glyph manifest (see [`GlyphRegistry::parse`]) and attaches it to the font
+    ///
+    /// The font does not need to be loaded yet.
+    pub fn load_glyph_registry(
+        &mut self,
+        manifest_path: &str,
+        font_name: &str,
+    ) -> Result<(), LoadError> {
+        let path = manifest_path.to_owned();
+        let font_name = font_name.to_owned();
+        self.load_file(
+            manifest_path,
+            Box::new(move |data, app: &mut AppContext| {
+                let text = String::from_utf8_lossy(&data);
+                let glyphs = GlyphRegistry::parse(&text)
+                    .map_err(|e| LoadError::ParseError(format!("{} - {}", path, e)))?;
+                app.set_font_glyph_registry(&font_name, glyphs);
+                console(format!("glyph manifest load complete - {}", path));
+                Ok(())
+            }),
+        )
+    }
+
+    /// Attaches the glyph names to the font, so printers and markup use them for consoles with that font
+    ///
+    /// The font does not need to be loaded yet.
+    pub fn set_font_glyph_registry(&mut self, font_name: &str, glyphs: GlyphRegistry) {
+        let glyphs = Rc::new(glyphs);
+        if let Some(font) = self.fonts.get(font_name) {
+            font.set_glyph_registry(Some(glyphs.clone()));
+        }
+        self.glyphs.insert(font_name.to_owned(), glyphs);
+    }
+
     pub fn insert_font(&mut self, name: &str, font: Rc<Font>) {
+        if let Some(codepage) = self.codepages.get(name) {
+            font.set_codepage(Some(codepage.clone()));
+        }
+        if let Some(glyphs) = self.glyphs.get(name) {
+            font.set_glyph_registry(Some(glyphs.clone()));
+        }
         self.fonts.insert(name.to_owned(), font);
     }
 
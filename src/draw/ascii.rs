@@ -0,0 +1,352 @@
+use crate::simple::{Buffer, Glyph};
+use crate::{Font, Image, RGBA};
+
+/// Constructs a new [`AsciiArt`] drawer for this buffer
+pub fn ascii_art(buffer: &mut Buffer) -> AsciiArt<'_> {
+    AsciiArt::new(buffer)
+}
+
+/// How image cells are compared to the font glyphs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    /// Match on brightness only, the fg and bg are shades of grey
+    Luminance,
+    /// Match on each of the R,G,B components, the fg and bg are the best fitting colors
+    Color,
+}
+
+/// The coverage of a single glyph, precomputed for matching
+struct GlyphMask {
+    glyph: Glyph,
+    coverage: Vec<f32>,
+    // sums used in the least squares fit
+    aa: f32,
+    cc: f32,
+    ac: f32,
+}
+
+/// The glyph shapes of a font, ready to be matched against images
+///
+/// Building this reads the whole font atlas, so keep it around instead of making a new one every frame.
+pub struct GlyphMatcher {
+    char_size: (u32, u32),
+    masks: Vec<GlyphMask>,
+}
+
+impl GlyphMatcher {
+    /// Creates a matcher that uses all of the glyphs in the font
+    pub fn new(font: &Font) -> Self {
+        GlyphMatcher::from_atlas(font.atlas(), font.char_size())
+    }
+
+    /// Creates a matcher from a font atlas image with the given cell size
+    ///
+    /// The glyph coverage is taken from the alpha channel of the atlas.
+    pub fn from_atlas(atlas: &Image, char_size: (u32, u32)) -> Self {
+        let (cw, ch) = char_size;
+        let mut masks = Vec::new();
+        if cw == 0 || ch == 0 {
+            return GlyphMatcher { char_size, masks };
+        }
+        let per_line = atlas.width() / cw;
+        let count = per_line * (atlas.height() / ch);
+
+        for glyph in 0..count {
+            let left = (glyph % per_line) * cw;
+            let top = (glyph / per_line) * ch;
+            let mut coverage = Vec::with_capacity((cw * ch) as usize);
+            for y in 0..ch {
+                for x in 0..cw {
+                    let pixel = atlas.img().get_pixel(left + x, top + y);
+                    coverage.push(pixel[3] as f32 / 255.0);
+                }
+            }
+            masks.push(GlyphMask::new(glyph, coverage));
+        }
+
+        GlyphMatcher { char_size, masks }
+    }
+
+    /// Limits the matching to the given glyphs (e.g. to skip letters or unprintable codes)
+    pub fn glyphs(mut self, glyphs: &[Glyph]) -> Self {
+        self.masks.retain(|m| glyphs.contains(&m.glyph));
+        self
+    }
+
+    /// The size of each glyph in pixels
+    pub fn char_size(&self) -> (u32, u32) {
+        self.char_size
+    }
+
+    /// Finds the glyph, fg and bg that best match the pixels (char_size, row major)
+    pub fn best_match(&self, pixels: &[RGBA], mode: MatchMode) -> (Glyph, RGBA, RGBA) {
+        let channels: Vec<Vec<f32>> = match mode {
+            MatchMode::Luminance => vec![pixels.iter().map(|p| p.luminance() as f32).collect()],
+            MatchMode::Color => vec![
+                pixels.iter().map(|p| p.0 as f32).collect(),
+                pixels.iter().map(|p| p.1 as f32).collect(),
+                pixels.iter().map(|p| p.2 as f32).collect(),
+            ],
+        };
+
+        let mut best_error = f32::MAX;
+        let mut best: Option<(Glyph, Vec<(f32, f32)>)> = None;
+        for mask in self.masks.iter() {
+            let mut error = 0.0;
+            let mut fits = Vec::with_capacity(channels.len());
+            for target in channels.iter() {
+                let (f, b, e) = mask.fit(target);
+                error += e;
+                fits.push((f, b));
+            }
+            if error < best_error {
+                best_error = error;
+                best = Some((mask.glyph, fits));
+            }
+        }
+
+        match best {
+            None => (0, RGBA::rgb(255, 255, 255), RGBA::rgb(0, 0, 0)),
+            Some((glyph, fits)) => {
+                let (fg, bg) = match mode {
+                    MatchMode::Luminance => {
+                        let (f, b) = (to_channel(fits[0].0), to_channel(fits[0].1));
+                        (RGBA::rgb(f, f, f), RGBA::rgb(b, b, b))
+                    }
+                    MatchMode::Color => (
+                        RGBA::rgb(
+                            to_channel(fits[0].0),
+                            to_channel(fits[1].0),
+                            to_channel(fits[2].0),
+                        ),
+                        RGBA::rgb(
+                            to_channel(fits[0].1),
+                            to_channel(fits[1].1),
+                            to_channel(fits[2].1),
+                        ),
+                    ),
+                };
+                (glyph, fg, bg)
+            }
+        }
+    }
+}
+
+// the fits are least squares values, so round them to the nearest color
+fn to_channel(value: f32) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+impl GlyphMask {
+    fn new(glyph: Glyph, coverage: Vec<f32>) -> Self {
+        let mut aa = 0.0;
+        let mut cc = 0.0;
+        let mut ac = 0.0;
+        for a in coverage.iter() {
+            let c = 1.0 - a;
+            aa += a * a;
+            cc += c * c;
+            ac += a * c;
+        }
+        GlyphMask {
+            glyph,
+            coverage,
+            aa,
+            cc,
+            ac,
+        }
+    }
+
+    /// Least squares fit of fg (f) and bg (b) so that coverage * f + (1 - coverage) * b ~= target
+    ///
+    /// Returns (f, b, squared error)
+    fn fit(&self, target: &[f32]) -> (f32, f32, f32) {
+        let mut at = 0.0;
+        let mut ct = 0.0;
+        let mut tt = 0.0;
+        for (a, t) in self.coverage.iter().zip(target.iter()) {
+            at += a * t;
+            ct += (1.0 - a) * t;
+            tt += t * t;
+        }
+
+        let det = self.aa * self.cc - self.ac * self.ac;
+        let (f, b) = if det.abs() < 0.0001 {
+            // glyph is a single tone, so both colors are the average
+            let mean = (at + ct) / target.len().max(1) as f32;
+            (mean, mean)
+        } else {
+            (
+                ((at * self.cc - ct * self.ac) / det).clamp(0.0, 255.0),
+                ((ct * self.aa - at * self.ac) / det).clamp(0.0, 255.0),
+            )
+        };
+
+        let error = tt - 2.0 * f * at - 2.0 * b * ct
+            + f * f * self.aa
+            + 2.0 * f * b * self.ac
+            + b * b * self.cc;
+        (f, b, error)
+    }
+}
+
+/// Draws images as glyphs + fg + bg, picking the glyph that looks most like each part of the image
+///
+/// Unlike [`crate::draw::Blitter`], which only sets the bg, this uses the shapes of the font glyphs.
+pub struct AsciiArt<'a> {
+    buffer: &'a mut Buffer,
+    mode: MatchMode,
+}
+
+impl<'a> AsciiArt<'a> {
+    /// Constructs a new AsciiArt drawer for this buffer
+    pub fn new(buffer: &'a mut Buffer) -> Self {
+        AsciiArt {
+            buffer,
+            mode: MatchMode::Color,
+        }
+    }
+
+    /// Sets the match mode, default=[`MatchMode::Color`]
+    pub fn mode(mut self, mode: MatchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Converts the image and draws it with the top left at x,y
+    ///
+    /// The image is scaled to fit width x height cells, default=one pixel per font pixel
+    pub fn blit(
+        &mut self,
+        matcher: &GlyphMatcher,
+        image: &Image,
+        x: i32,
+        y: i32,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) {
+        let (cw, ch) = matcher.char_size();
+        if cw == 0 || ch == 0 || image.width() == 0 || image.height() == 0 {
+            return;
+        }
+        let width = width.unwrap_or(image.width() / cw);
+        let height = height.unwrap_or(image.height() / ch);
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        // image pixels per font pixel
+        let step_x = image.width() as f32 / (width * cw) as f32;
+        let step_y = image.height() as f32 / (height * ch) as f32;
+
+        let mut pixels = Vec::with_capacity((cw * ch) as usize);
+        for cy in 0..height {
+            for cx in 0..width {
+                if !self.buffer.has_xy(x + cx as i32, y + cy as i32) {
+                    continue;
+                }
+                pixels.clear();
+                for py in 0..ch {
+                    let iy = (((cy * ch + py) as f32 + 0.5) * step_y) as u32;
+                    for px in 0..cw {
+                        let ix = (((cx * cw + px) as f32 + 0.5) * step_x) as u32;
+                        let p = image
+                            .img()
+                            .get_pixel(ix.min(image.width() - 1), iy.min(image.height() - 1));
+                        pixels.push(RGBA::rgba(p[0], p[1], p[2], p[3]));
+                    }
+                }
+
+                let (glyph, fg, bg) = matcher.best_match(&pixels, self.mode);
+                self.buffer
+                    .draw(x + cx as i32, y + cy as i32, glyph, fg, bg);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const WHITE: RGBA = RGBA::rgb(255, 255, 255);
+    const BLACK: RGBA = RGBA::rgb(0, 0, 0);
+    const RED: RGBA = RGBA::rgb(255, 0, 0);
+    const CLEAR: RGBA = RGBA::rgba(0, 0, 0, 0);
+
+    // 3 glyphs of 2x2: empty, left half, top half
+    fn test_matcher() -> GlyphMatcher {
+        let mut atlas = Image::empty(6, 2);
+        for (x, y) in [(2, 0), (2, 1), (4, 0), (5, 0)] {
+            atlas.put_pixel(x, y, WHITE);
+        }
+        for (x, y) in [
+            (0, 0),
+            (1, 0),
+            (0, 1),
+            (1, 1),
+            (3, 0),
+            (3, 1),
+            (4, 1),
+            (5, 1),
+        ] {
+            atlas.put_pixel(x, y, CLEAR);
+        }
+        GlyphMatcher::from_atlas(&atlas, (2, 2))
+    }
+
+    #[test]
+    fn match_color() {
+        let matcher = test_matcher();
+
+        let (glyph, fg, bg) = matcher.best_match(&[RED, BLACK, RED, BLACK], MatchMode::Color);
+        assert_eq!(glyph, 1);
+        assert_eq!(fg, RED);
+        assert_eq!(bg, BLACK);
+
+        let (glyph, fg, bg) = matcher.best_match(&[BLACK, BLACK, WHITE, WHITE], MatchMode::Color);
+        assert_eq!(glyph, 2);
+        assert_eq!(fg, BLACK);
+        assert_eq!(bg, WHITE);
+    }
+
+    #[test]
+    fn match_luminance() {
+        let matcher = test_matcher().glyphs(&[0, 2]);
+
+        let (glyph, fg, bg) = matcher.best_match(&[RED, BLACK, RED, BLACK], MatchMode::Luminance);
+        assert_eq!(glyph, 0);
+        assert_eq!(fg, bg);
+    }
+
+    #[test]
+    fn match_rounds() {
+        let matcher = test_matcher().glyphs(&[0]);
+        let dark = RGBA::rgb(10, 10, 10);
+        let light = RGBA::rgb(11, 11, 11);
+
+        // the mean is 10.75, which rounds up
+        let (_, fg, bg) = matcher.best_match(&[dark, light, light, light], MatchMode::Color);
+        assert_eq!(fg, light);
+        assert_eq!(bg, light);
+    }
+
+    #[test]
+    fn blit_image() {
+        let matcher = test_matcher();
+        let mut image = Image::empty(4, 2);
+        for (x, y) in [(0, 0), (0, 1), (2, 0), (3, 0)] {
+            image.put_pixel(x, y, WHITE);
+        }
+        for (x, y) in [(1, 0), (1, 1), (2, 1), (3, 1)] {
+            image.put_pixel(x, y, BLACK);
+        }
+
+        let mut buffer = Buffer::new(10, 10);
+        ascii_art(&mut buffer).blit(&matcher, &image, 1, 1, None, None);
+        assert_eq!(buffer.get_glyph(1, 1), Some(&1));
+        assert_eq!(buffer.get_glyph(2, 1), Some(&2));
+        assert_eq!(buffer.get_fore(2, 1), Some(&WHITE));
+        assert_eq!(buffer.get_back(2, 1), Some(&BLACK));
+    }
+}
@@ -1,12 +1,60 @@
-use super::TextAlign;
+use super::{fade_fg, justify_spaces, Overflow, TextAlign, VerticalAlign};
 use crate::simple::Buffer;
-use crate::simple::Glyph;
+use crate::simple::{parse_glyph_code, Glyph, WIDE_GLYPH};
 use crate::text::parse_colored_lines;
-use crate::text::wrap_colored;
+use crate::text::wrap_colored_line;
 use crate::text::ColoredLine;
-use crate::text::ColoredSpan;
+use crate::text::RichText;
+use crate::text::{fix_wide_halves, text_cells, EffectCell, TextEffect};
 use crate::{to_rgba, RGBA};
 use std::cmp::{max, min};
+use std::mem::discriminant;
+
+// The glyph, fg, and bg to draw in a cell
+#[derive(Clone)]
+struct Cell {
+    glyph: Glyph,
+    fg: Option<RGBA>,
+    bg: Option<RGBA>,
+    // spaces get stretched when justifying
+    space: bool,
+    effect: Option<TextEffect>,
+    // set by the effect, the number of rows to move the glyph
+    dy: i32,
+}
+
+impl Cell {
+    fn new(glyph: Glyph, fg: Option<RGBA>, bg: Option<RGBA>) -> Self {
+        Cell {
+            glyph,
+            fg,
+            bg,
+            space: false,
+            effect: None,
+            dy: 0,
+        }
+    }
+
+    // wide chars take 2 cells (see `text_cells`)
+    fn text(
+        to_glyph: &dyn Fn(char) -> Glyph,
+        text: &str,
+        fg: Option<RGBA>,
+        bg: Option<RGBA>,
+    ) -> Vec<Self> {
+        text_cells(text)
+            .into_iter()
+            .map(|(ch, flags)| Cell {
+                glyph: to_glyph(ch) | flags,
+                fg,
+                bg,
+                space: ch == ' ',
+                effect: None,
+                dy: 0,
+            })
+            .collect()
+    }
+}
 
 /// Creates a [`ColoredPrinter`]
 pub fn colored<'a>(buffer: &'a mut Buffer) -> ColoredPrinter {
@@ -14,15 +62,29 @@ pub fn colored<'a>(buffer: &'a mut Buffer) -> ColoredPrinter {
 }
 
 /// Prints color encoded text to the buffer
+///
+/// See [`crate::text::parse_colored_line_ex`] for the markup.  Bad markup is printed as plain text.
+///
+/// Spans with a [`TextEffect`] (e.g. `#[~wave]`) are animated using the time given to [`ColoredPrinter::time`],
+/// so print them every frame.
 pub struct ColoredPrinter<'a> {
     buffer: &'a mut Buffer,
     width: Option<i32>,
     height: Option<i32>,
     align: TextAlign,
+    valign: VerticalAlign,
+    overflow: Overflow,
+    ellipsis: &'a str,
     fg: Option<RGBA>,
     bg: Option<RGBA>,
-    to_glyph: &'a dyn Fn(char) -> Glyph,
+    font: Option<u8>,
+    to_glyph: Option<&'a dyn Fn(char) -> Glyph>,
     to_rgba: &'a dyn Fn(&str) -> Option<RGBA>,
+    glyph_names: &'a dyn Fn(&str) -> Option<Glyph>,
+    time: f32,
+    effects: &'a dyn Fn(&str) -> Option<TextEffect>,
+    // glyphs that effects moved to another row, drawn after the text
+    moved: Vec<(i32, i32, Glyph, Option<RGBA>)>,
 }
 
 impl<'a> ColoredPrinter<'a> {
@@ -33,10 +95,18 @@ impl<'a> ColoredPrinter<'a> {
             width: None,
             height: None,
             align: TextAlign::Left,
+            valign: VerticalAlign::Top,
+            overflow: Overflow::Clip,
+            ellipsis: "...",
             fg: Some(RGBA::rgb(255, 255, 255)),
             bg: None,
-            to_glyph: &|ch| ch as u32,
+            font: None,
+            to_glyph: None,
             to_rgba: &to_rgba,
+            glyph_names: &|_| None,
+            time: 0.0,
+            effects: &TextEffect::from_name,
+            moved: Vec::new(),
         }
     }
 
@@ -65,6 +135,26 @@ impl<'a> ColoredPrinter<'a> {
         self
     }
 
+    /// Sets the vertical alignment of the lines in the height, default=Top
+    ///
+    /// Used by `print_lines`, `wrap`, `print_rich`, and `wrap_rich`
+    pub fn valign(mut self, valign: VerticalAlign) -> Self {
+        self.valign = valign;
+        self
+    }
+
+    /// Sets what happens to text that does not fit the width (or the height when wrapping), default=Clip
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Sets the text used by the ellipsis overflow policies, default="..."
+    pub fn ellipsis(mut self, ellipsis: &'a str) -> Self {
+        self.ellipsis = ellipsis;
+        self
+    }
+
     /// Sets the fg (default=WHITE)
     pub fn fg(mut self, fg: RGBA) -> Self {
         self.fg = Some(fg);
@@ -77,9 +167,15 @@ impl<'a> ColoredPrinter<'a> {
         self
     }
 
-    /// Sets the char->Glyph conversion function, default=(ch as u32)
+    /// Sets the font slot of the printed cells (see [`Buffer::font`]), default=None (the console's font)
+    pub fn font(mut self, font: u8) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// Sets the char->Glyph conversion function, default=the buffer's codepage (from the console's font) or (ch as u32)
     pub fn to_glyph(mut self, to_glyph: &'a dyn Fn(char) -> Glyph) -> Self {
-        self.to_glyph = to_glyph;
+        self.to_glyph = Some(to_glyph);
         self
     }
 
@@ -89,6 +185,28 @@ impl<'a> ColoredPrinter<'a> {
         self
     }
 
+    /// Sets the lookup for named inline glyphs (`#{name}`), default=no names
+    ///
+    /// Names it does not know are looked up in the buffer's glyph registry (from the console's font),
+    /// which also gives their default colors (unless the markup sets a color).
+    /// Numbered inline glyphs (`#{219}` or `#{0xDB}`) do not use this.
+    pub fn glyph_names(mut self, glyph_names: &'a dyn Fn(&str) -> Option<Glyph>) -> Self {
+        self.glyph_names = glyph_names;
+        self
+    }
+
+    /// Sets the elapsed time (in seconds) that drives the text effects, default=0
+    pub fn time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// Sets the lookup for effect names (`#[~name]`), default=[`TextEffect::from_name`]
+    pub fn effects(mut self, effects: &'a dyn Fn(&str) -> Option<TextEffect>) -> Self {
+        self.effects = effects;
+        self
+    }
+
     /// Prints the given text at the given location, returns the length printed
     pub fn print(&mut self, x: i32, y: i32, text: &str) -> i32 {
         // let width = self.width.unwrap_or(self.buffer.width() as i32 - x);
@@ -104,63 +222,181 @@ impl<'a> ColoredPrinter<'a> {
         if let Some(height) = self.height {
             for _ in 1..height {
                 for ix in 0..widest {
-                    self.buffer.draw_opt(x + ix, cy, Some(0), self.fg, self.bg);
+                    self.buffer
+                        .draw_opt_font(x + ix, cy, Some(0), self.fg, self.bg, self.font);
                 }
                 cy += 1;
             }
         }
 
+        self.draw_moved();
         widest
     }
 
     /// Prints all the lines in the given text, truncates at width (if any), returns the (width,height) printed
     pub fn print_lines(&mut self, x: i32, y: i32, text: &str) -> (i32, i32) {
-        // let width = self.width.unwrap_or(self.buffer.width() as i32 - x);
-        let max_height = self.height.unwrap_or(999);
+        let rows: Vec<(Vec<Cell>, bool)> = parse_colored_lines(text)
+            .iter()
+            .map(|line| (self.line_cells(line), false))
+            .collect();
+        self.print_rows(x, y, rows)
+    }
 
-        let mut widest = 0;
+    /// Performs word wrapping of the given text at the setup width (or buffer width) and prints the lines
+    pub fn wrap(&mut self, x: i32, y: i32, text: &str) -> (i32, i32) {
+        let width = self.width.unwrap_or(self.buffer.width() as i32 - x);
 
-        let mut cy = y;
-        for (i, line) in parse_colored_lines(text).iter().enumerate() {
-            if i as i32 >= max_height {
-                break;
+        let mut rows = Vec::new();
+        for paragraph in parse_colored_lines(text) {
+            let lines = wrap_colored_line(width as usize, paragraph);
+            let count = lines.len();
+            for (i, line) in lines.iter().enumerate() {
+                rows.push((self.line_cells(line), i + 1 < count));
             }
-            let w = self.print_line(x, cy, &line);
-            widest = max(widest, w);
-            cy += 1;
         }
+        self.print_rows(x, y, rows)
+    }
 
-        (widest, cy - y)
+    /// Prints the rich text at the given location, truncates at width (if any), returns the (width,height) printed
+    pub fn print_rich(&mut self, x: i32, y: i32, text: &RichText) -> (i32, i32) {
+        let rows: Vec<(Vec<Cell>, bool)> = text
+            .lines()
+            .iter()
+            .map(|line| (self.rich_cells(line), false))
+            .collect();
+        self.print_rows(x, y, rows)
     }
 
-    /// Performs word wrapping of the given text at the setup width (or buffer width) and prints the lines
-    pub fn wrap(&mut self, x: i32, y: i32, text: &str) -> (i32, i32) {
+    /// Performs word wrapping of the rich text at the setup width (or buffer width) and prints the lines
+    pub fn wrap_rich(&mut self, x: i32, y: i32, text: &RichText) -> (i32, i32) {
         let width = self.width.unwrap_or(self.buffer.width() as i32 - x);
-        let max_height = self.height.unwrap_or(999);
+
+        let mut rows = Vec::new();
+        for paragraph in text.lines() {
+            let lines = paragraph.wrap(width as usize);
+            let count = lines.len();
+            for (i, line) in lines.iter().enumerate() {
+                rows.push((self.rich_cells(line), i + 1 < count));
+            }
+        }
+        self.print_rows(x, y, rows)
+    }
+
+    /// Prints the rows (cells, can_justify) in the height with the vertical alignment
+    fn print_rows(&mut self, x: i32, y: i32, mut rows: Vec<(Vec<Cell>, bool)>) -> (i32, i32) {
+        let max_height = self.height.unwrap_or(999).max(0) as usize;
+        let more = rows.len() > max_height;
+        rows.truncate(max_height);
+        self.apply_effects(&mut rows);
 
         let mut widest = 0;
 
-        let mut cy = y;
-        for (i, line) in wrap_colored(width as usize, text).iter().enumerate() {
-            if i as i32 >= max_height {
-                break;
-            }
-            let w = self.print_line(x, cy, &line);
+        let count = rows.len();
+        let mut cy = y + self.valign.offset(count as i32, self.height);
+        for (i, (cells, can_justify)) in rows.iter().enumerate() {
+            let cut = more && i + 1 == count;
+            let w = self.print_cells(x, cy, cells, *can_justify, cut);
             widest = max(widest, w);
             cy += 1;
         }
 
+        self.draw_moved();
         (widest, cy - y)
     }
 
     /// Prints the line, handles width, bg, and align
     fn print_line(&mut self, x: i32, y: i32, line: &ColoredLine) -> i32 {
-        let width = self.width.unwrap_or(line.char_len() as i32);
-        let self_len = min(width, line.char_len() as i32);
-        let spaces = width.saturating_sub(self_len);
+        let mut rows = vec![(self.line_cells(line), false)];
+        self.apply_effects(&mut rows);
+        self.print_cells(x, y, &rows[0].0, false, false)
+    }
+
+    /// Applies the effects of the cells at the current time
+    ///
+    /// The index of each char counts from the start of its effect, continuing across rows.
+    fn apply_effects(&self, rows: &mut [(Vec<Cell>, bool)]) {
+        let mut index = 0;
+        let mut last = None;
+        for cell in rows.iter_mut().flat_map(|(cells, _)| cells.iter_mut()) {
+            let effect = match cell.effect {
+                None => {
+                    last = None;
+                    continue;
+                }
+                Some(effect) => effect,
+            };
+            let kind = Some(discriminant(&effect));
+            if kind != last {
+                index = 0;
+                last = kind;
+            }
+
+            let mut out = EffectCell::new(cell.fg, cell.bg);
+            effect.apply(self.time, index, &mut out);
+            // both halves of a wide glyph get the same index
+            if cell.glyph & WIDE_GLYPH == 0 {
+                index += 1;
+            }
+            if !out.visible {
+                cell.glyph = 0;
+            }
+            cell.fg = out.fg;
+            cell.bg = out.bg;
+            cell.dy = out.dy;
+        }
+    }
+
+    /// Draws a cell, glyphs moved by an effect leave the bg behind and are drawn at the end
+    fn draw_cell(&mut self, x: i32, y: i32, cell: &Cell) {
+        match cell.dy {
+            0 => self
+                .buffer
+                .draw_opt_font(x, y, Some(cell.glyph), cell.fg, cell.bg, self.font),
+            dy => {
+                self.buffer
+                    .draw_opt_font(x, y, Some(0), cell.fg, cell.bg, self.font);
+                self.moved.push((x, y + dy, cell.glyph, cell.fg));
+            }
+        }
+    }
+
+    /// Draws the glyphs that effects moved, over whatever is in those cells
+    fn draw_moved(&mut self) {
+        for (x, y, glyph, fg) in std::mem::take(&mut self.moved) {
+            self.buffer
+                .draw_opt_font(x, y, Some(glyph), fg, None, self.font);
+        }
+    }
+
+    /// Prints the cells of a line, handles width, bg, align, and overflow
+    ///
+    /// more means there are more lines that did not fit the height
+    fn print_cells(
+        &mut self,
+        x: i32,
+        y: i32,
+        cells: &[Cell],
+        can_justify: bool,
+        more: bool,
+    ) -> i32 {
+        let limit = self.width.unwrap_or(i32::MAX);
+        let cells = self.fit_cells(cells, limit, more);
+        let width = self.width.unwrap_or(cells.len() as i32);
+        let self_len = min(width, cells.len() as i32);
+        let mut spaces = width.saturating_sub(self_len);
+
+        let gaps = cells.iter().filter(|c| c.space).count() as i32;
+        let (extra, gaps) = match self.align {
+            TextAlign::Justify if can_justify && !more && gaps > 0 => {
+                let extra = spaces;
+                spaces = 0;
+                (extra, gaps)
+            }
+            _ => (0, 0),
+        };
 
         let (x, pre, post) = match self.align {
-            TextAlign::Left => (x, 0, spaces),
+            TextAlign::Left | TextAlign::Justify => (x, 0, spaces),
             TextAlign::Center => {
                 let half = spaces / 2;
                 (x - half - self_len / 2, half, spaces - half)
@@ -172,57 +408,154 @@ impl<'a> ColoredPrinter<'a> {
         let fg = self.fg;
         let bg = self.bg;
 
-        // let mut output = "[".to_string();
         for _ in 0..pre {
-            self.buffer.draw_opt(cx, y, Some(0), fg, bg);
+            self.buffer.draw_opt_font(cx, y, Some(0), fg, bg, self.font);
             cx += 1;
         }
 
-        // output += line.0;
-        let mut left = self_len as u32;
-        for span in line.spans() {
-            let w = self.print_span(cx, y, span, left);
-            left = left.saturating_sub(w as u32);
-            cx += w;
-            if left == 0 {
-                // post = 0;
-                break;
+        let mut gap = 0;
+        for cell in cells.iter().take(self_len.max(0) as usize) {
+            self.draw_cell(cx, y, cell);
+            cx += 1;
+            if cell.space {
+                for _ in 0..justify_spaces(extra, gaps, gap) {
+                    self.buffer
+                        .draw_opt_font(cx, y, Some(cell.glyph), cell.fg, cell.bg, self.font);
+                    cx += 1;
+                }
+                gap += 1;
             }
         }
 
         for _ in 0..post {
-            self.buffer.draw_opt(cx, y, Some(0), fg, bg);
+            self.buffer.draw_opt_font(cx, y, Some(0), fg, bg, self.font);
             cx += 1;
         }
 
-        // output.push(']');
-
-        // println!("{} [{}]", output, output.len() - 2);
         width
     }
 
-    /// just print the text - nothing more, nothing less
-    /// decisions about padding, alignment, etc... need to be in ColoredLine::print
-    fn print_span(&mut self, x: i32, y: i32, span: &ColoredSpan, width: u32) -> i32 {
-        let mut cx = x;
-        let fg = match span.color() {
-            None => self.fg,
-            Some(txt) => (self.to_rgba)(txt),
+    /// Applies the overflow policy to the cells
+    fn fit_cells(&self, cells: &[Cell], width: i32, more: bool) -> Vec<Cell> {
+        let ellipsis = |last: Option<&Cell>| -> Vec<Cell> {
+            let (fg, bg) = match last {
+                None => (self.fg, self.bg),
+                Some(cell) => (cell.fg, cell.bg),
+            };
+            Cell::text(
+                &|ch| self.buffer.glyph_with(self.to_glyph, ch),
+                self.ellipsis,
+                fg,
+                bg,
+            )
         };
-        let bg = self.bg;
+        let (mut cells, fade) = self
+            .overflow
+            .apply(cells, width.max(0) as usize, more, ellipsis);
+        let space = self.buffer.glyph_with(self.to_glyph, ' ');
+        fix_wide_halves(
+            &mut cells,
+            |c| c.glyph,
+            |c| *c = Cell::new(space, c.fg, c.bg),
+        );
+        let len = cells.len();
+        for (i, cell) in cells.iter_mut().enumerate().skip(len - fade) {
+            cell.fg = fade_fg(cell.fg, len - i - 1);
+        }
+        cells
+    }
 
-        let mut left = width;
-        for char in span.as_str().chars() {
-            let glyph = (self.to_glyph)(char);
-            self.buffer.draw_opt(cx, y, Some(glyph), fg, bg);
-            cx += 1;
-            left = left.saturating_sub(1);
-            if left == 0 {
-                break;
+    /// Converts the spans of the line into the glyph, fg, and bg to draw
+    fn line_cells(&self, line: &ColoredLine) -> Vec<Cell> {
+        let mut cells = Vec::with_capacity(line.char_len());
+        for span in line.spans() {
+            let fg = match span.fg() {
+                None => self.fg,
+                Some(txt) => (self.to_rgba)(txt),
+            };
+            let bg = match span.bg() {
+                None => self.bg,
+                Some(txt) => (self.to_rgba)(txt),
+            };
+
+            let effect = span.effect().and_then(self.effects);
+
+            let start = cells.len();
+            match span.glyph() {
+                Some(code) => {
+                    let (glyph, def_fg, def_bg) = self.inline_glyph(code);
+                    let fg = match span.fg() {
+                        None => def_fg.or(fg),
+                        Some(_) => fg,
+                    };
+                    let bg = match span.bg() {
+                        None => def_bg.or(bg),
+                        Some(_) => bg,
+                    };
+                    cells.push(Cell::new(glyph, fg, bg));
+                }
+                None => {
+                    cells.extend(Cell::text(
+                        &|ch| self.buffer.glyph_with(self.to_glyph, ch),
+                        span.as_str(),
+                        fg,
+                        bg,
+                    ));
+                }
+            }
+            for cell in cells[start..].iter_mut() {
+                cell.effect = effect;
             }
         }
+        cells
+    }
 
-        cx - x
+    /// Converts the spans of the rich text into the glyph, fg, and bg to draw
+    fn rich_cells(&self, text: &RichText) -> Vec<Cell> {
+        let mut cells = Vec::with_capacity(text.char_len());
+        for span in text.spans() {
+            let fg = span.get_fg().or(self.fg);
+            let bg = span.get_bg().or(self.bg);
+
+            let start = cells.len();
+            match (span.get_glyph(), span.get_glyph_name()) {
+                (Some(glyph), _) => cells.push(Cell::new(glyph, fg, bg)),
+                (None, Some(name)) => {
+                    // the span colors win over the glyph's default colors
+                    let (glyph, def_fg, def_bg) = self.inline_glyph(name);
+                    let fg = span.get_fg().or(def_fg).or(self.fg);
+                    let bg = span.get_bg().or(def_bg).or(self.bg);
+                    cells.push(Cell::new(glyph, fg, bg));
+                }
+                (None, None) => {
+                    cells.extend(Cell::text(
+                        &|ch| self.buffer.glyph_with(self.to_glyph, ch),
+                        span.as_str(),
+                        fg,
+                        bg,
+                    ));
+                }
+            }
+            for cell in cells[start..].iter_mut() {
+                cell.effect = span.get_effect();
+            }
+        }
+        cells
+    }
+
+    /// Converts the code or name from an inline glyph to the glyph and its default fg and bg - unknown names become '?'
+    fn inline_glyph(&self, code: &str) -> (Glyph, Option<RGBA>, Option<RGBA>) {
+        let number = parse_glyph_code(code);
+        let registered = || {
+            self.buffer
+                .glyph_def(code)
+                .map(|def| (def.glyph, def.fg, def.bg))
+        };
+        number
+            .or_else(|| (self.glyph_names)(code))
+            .map(|glyph| (glyph, None, None))
+            .or_else(registered)
+            .unwrap_or_else(|| (self.buffer.glyph_with(self.to_glyph, '?'), None, None))
     }
 }
 
@@ -230,6 +563,8 @@ impl<'a> ColoredPrinter<'a> {
 mod test {
 
     use super::*;
+    use crate::simple::{WIDE_CONTINUATION, WIDE_GLYPH};
+    use crate::text::RichSpan;
 
     const _WHITE: RGBA = RGBA::rgb(255, 255, 255);
     const _RED: RGBA = RGBA::rgb(255, 0, 0);
@@ -422,4 +757,179 @@ mod test {
         );
         assert_eq!(r, (20, 5));
     }
+
+    #[test]
+    fn print_fg_bg_glyphs() {
+        let mut buffer = Buffer::new(50, 50);
+        let names = |name: &str| match name {
+            "heart" => Some(3),
+            _ => None,
+        };
+        let mut printer = colored(&mut buffer).bg(_BLACK).glyph_names(&names);
+
+        assert_eq!(
+            printer.print(0, 0, "a#[#f00:#00f]#{219}#{0x10}#[]#{heart}#{nope}"),
+            5
+        );
+        assert_eq!(buffer.get_glyph(1, 0), Some(&219));
+        assert_eq!(buffer.get_fore(1, 0), Some(&_RED));
+        assert_eq!(buffer.get_back(1, 0), Some(&_BLUE));
+        assert_eq!(buffer.get_glyph(2, 0), Some(&16));
+        assert_eq!(buffer.get_glyph(3, 0), Some(&3));
+        assert_eq!(buffer.get_back(3, 0), Some(&_BLACK));
+        assert_eq!(buffer.get_glyph(4, 0), Some(&('?' as u32)));
+    }
+
+    #[test]
+    fn glyph_registry() {
+        let mut registry = crate::GlyphRegistry::new();
+        registry.insert("player", crate::GlyphDef::new(64).fg(_RED).bg(_BLUE));
+        registry.insert("floor", crate::GlyphDef::new(250));
+        let mut buffer = Buffer::new(50, 50);
+        buffer.set_glyph_registry(Some(std::rc::Rc::new(registry)));
+
+        let mut printer = colored(&mut buffer).fg(_WHITE);
+        assert_eq!(printer.print(0, 0, "#{player}#{floor}#[#0f0]#{player}"), 3);
+        assert_eq!(buffer.get_glyph(0, 0), Some(&64));
+        assert_eq!(buffer.get_fore(0, 0), Some(&_RED));
+        assert_eq!(buffer.get_back(0, 0), Some(&_BLUE));
+        assert_eq!(buffer.get_glyph(1, 0), Some(&250));
+        assert_eq!(buffer.get_fore(1, 0), Some(&_WHITE));
+        // the markup color wins
+        assert_eq!(buffer.get_fore(2, 0), Some(&_GREEN));
+        assert_eq!(buffer.get_back(2, 0), Some(&_BLUE));
+
+        // rich text keeps the names until it is printed
+        let text = crate::text::RichText::from_markup("#{floor}#[#0f0]#{player}#[]#{taco}");
+        let mut printer = colored(&mut buffer).fg(_WHITE);
+        printer.print_rich(0, 2, &text);
+        assert_eq!(buffer.get_glyph(0, 2), Some(&250));
+        assert_eq!(buffer.get_glyph(1, 2), Some(&64));
+        assert_eq!(buffer.get_fore(1, 2), Some(&_GREEN));
+        assert_eq!(buffer.get_back(1, 2), Some(&_BLUE));
+        assert_eq!(buffer.get_glyph(2, 2), Some(&('?' as u32)));
+
+        let mut printer = crate::draw::plain(&mut buffer).fg(_WHITE).bg(_BLACK);
+        assert!(printer.print_glyph(0, 1, "player"));
+        assert!(printer.print_glyph(1, 1, "floor"));
+        assert!(!printer.print_glyph(2, 1, "taco"));
+        assert_eq!(buffer.get_fore(0, 1), Some(&_RED));
+        assert_eq!(buffer.get_back(1, 1), Some(&_BLACK));
+        assert_eq!(buffer.get_glyph(2, 1), Some(&0));
+    }
+
+    #[test]
+    fn print_rich() {
+        let mut buffer = Buffer::new(50, 50);
+        let mut printer = colored(&mut buffer).width(10).bg(_BLACK);
+
+        let text = RichText::plain("taco ")
+            + RichSpan::new("casa").fg(_RED).bg(_BLUE)
+            + RichSpan::glyph(3)
+            + " is a great fast food place";
+        assert_eq!(printer.wrap_rich(0, 1, &text), (10, 4));
+        assert_eq!(extract_line(&buffer, 0, 1, 11), "taco casa\u{3}\0");
+        assert_eq!(extract_line(&buffer, 0, 2, 11), "is a great\0");
+        assert_eq!(buffer.get_fore(5, 1), Some(&_RED));
+        assert_eq!(buffer.get_back(5, 1), Some(&_BLUE));
+        assert_eq!(buffer.get_back(9, 1), Some(&_BLACK));
+
+        let mut printer = colored(&mut buffer);
+        assert_eq!(printer.print_rich(0, 10, &text), (37, 1));
+    }
+
+    #[test]
+    fn wrap_justify_valign() {
+        let mut buffer = Buffer::new(50, 50);
+        let mut printer = colored(&mut buffer)
+            .width(12)
+            .height(7)
+            .align(TextAlign::Justify)
+            .valign(VerticalAlign::Middle);
+
+        let r = printer.wrap(
+            0,
+            0,
+            "taco #[#f00]is a great#[] deal for a bit\nfast food place",
+        );
+        assert_eq!(extract_line(&buffer, 0, 0, 12), "\0\0\0\0\0\0\0\0\0\0\0\0");
+        assert_eq!(extract_line(&buffer, 0, 1, 13), "taco   is  a\0");
+        assert_eq!(extract_line(&buffer, 0, 2, 13), "great   deal\0");
+        assert_eq!(extract_line(&buffer, 0, 3, 13), "for a bit\0\0\0\0");
+        assert_eq!(extract_line(&buffer, 0, 4, 13), "fast    food\0");
+        assert_eq!(extract_line(&buffer, 0, 5, 13), "place\0\0\0\0\0\0\0\0");
+        assert_eq!(buffer.get_fore(0, 2), Some(&_RED));
+        assert_eq!(r, (12, 6));
+    }
+
+    #[test]
+    fn overflow() {
+        let mut buffer = Buffer::new(50, 50);
+
+        colored(&mut buffer)
+            .width(12)
+            .overflow(Overflow::Ellipsis)
+            .print(0, 0, "/home/#[#f00]user/games#[]/saves/slot1.sav");
+        assert_eq!(extract_line(&buffer, 0, 0, 13), "/home/use...\0");
+        assert_eq!(buffer.get_fore(10, 0), Some(&_RED));
+
+        colored(&mut buffer)
+            .width(13)
+            .overflow(Overflow::MiddleEllipsis)
+            .print(0, 1, "/home/user/games/saves/slot1.sav");
+        assert_eq!(extract_line(&buffer, 0, 1, 14), "/home...1.sav\0");
+
+        let r = colored(&mut buffer)
+            .width(10)
+            .height(2)
+            .overflow(Overflow::Fade)
+            .wrap(0, 2, "taco casa is a great fast food place");
+        assert_eq!(extract_line(&buffer, 0, 3, 10), "is a great");
+        assert_eq!(buffer.get_fore(6, 3).unwrap().3, 255);
+        assert_eq!(buffer.get_fore(9, 3).unwrap().3, 63);
+        assert_eq!(r, (10, 2));
+    }
+
+    #[test]
+    fn wide_chars() {
+        let mut buffer = Buffer::new(50, 50);
+
+        let w = colored(&mut buffer).print(0, 0, "#[#f00]真棒#[] ok");
+        assert_eq!(w, 7);
+        assert_eq!(buffer.get_glyph(2, 0), Some(&('棒' as u32 | WIDE_GLYPH)));
+        assert_eq!(
+            buffer.get_glyph(3, 0),
+            Some(&('棒' as u32 | WIDE_CONTINUATION))
+        );
+        assert_eq!(buffer.get_fore(3, 0), Some(&_RED));
+        assert_eq!(extract_line(&buffer, 4, 0, 3), " ok");
+
+        colored(&mut buffer)
+            .width(6)
+            .overflow(Overflow::Ellipsis)
+            .print(0, 1, "#[#f00]真棒真棒");
+        assert_eq!(extract_line(&buffer, 2, 1, 4), " ...");
+        assert_eq!(buffer.get_fore(2, 1), Some(&_RED));
+    }
+
+    #[test]
+    fn effects() {
+        let mut buffer = Buffer::new(50, 50);
+
+        colored(&mut buffer)
+            .time(0.25)
+            .print(0, 0, "Hi #[#f00~type=10]there#[] you");
+        assert_eq!(extract_line(&buffer, 0, 0, 11), "Hi the\0\0 yo");
+        assert_eq!(buffer.get_fore(3, 0), Some(&_RED));
+
+        colored(&mut buffer).time(0.25).print(0, 2, "#[~wave]abcde");
+        assert_eq!(extract_line(&buffer, 0, 3, 1), "a");
+        assert_eq!(buffer.get_glyph(0, 2), Some(&0));
+        assert_eq!(extract_line(&buffer, 2, 2, 1), "c");
+        assert_eq!(extract_line(&buffer, 4, 1, 1), "e");
+
+        let text = RichText::plain("ok ") + RichSpan::new("go").effect(TextEffect::Blink(1.0));
+        colored(&mut buffer).time(0.75).print_rich(0, 5, &text);
+        assert_eq!(extract_line(&buffer, 0, 5, 5), "ok \0\0");
+    }
 }
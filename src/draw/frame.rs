@@ -45,7 +45,7 @@ pub struct Frame<'a> {
     title_fg: Option<RGBA>,
     title_align: TextAlign,
 
-    to_glyph: &'a dyn Fn(char) -> Glyph,
+    to_glyph: Option<&'a dyn Fn(char) -> Glyph>,
 }
 
 impl<'a> Frame<'a> {
@@ -66,7 +66,7 @@ impl<'a> Frame<'a> {
             title_fg: None,
             title_align: TextAlign::Center,
 
-            to_glyph: &codepage437::to_glyph,
+            to_glyph: None,
         }
     }
 
@@ -128,7 +128,12 @@ impl<'a> Frame<'a> {
             );
         }
 
-        let glyphs = self.border.glyphs(self.to_glyph);
+        // the buffer's codepage (from the console's font), or CP437
+        let glyphs = match (self.to_glyph, self.buffer.codepage()) {
+            (Some(to_glyph), _) => self.border.glyphs(to_glyph),
+            (None, Some(codepage)) => self.border.glyphs(&|ch| codepage.to_glyph(ch)),
+            (None, None) => self.border.glyphs(&codepage437::to_glyph),
+        };
         let left = x;
         let top = y;
         let right = x + width as i32 - 1;
@@ -159,7 +164,7 @@ impl<'a> Frame<'a> {
         let tw = self.title.len() as i32;
         if tw > 0 {
             let tx = match self.title_align {
-                TextAlign::Left => x + 2,
+                TextAlign::Left | TextAlign::Justify => x + 2,
                 TextAlign::Right => x + width as i32 - tw - 2,
                 TextAlign::Center => x + width as i32 / 2,
             };
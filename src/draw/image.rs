@@ -1,16 +1,44 @@
 use crate::simple::Buffer;
-use crate::Image;
 use crate::RGBA;
+use crate::{Image, SpriteSheet};
 
 /// Constructs a new [`Blitter`] for this buffer
 pub fn image(buffer: &mut Buffer) -> Blitter {
     Blitter::new(buffer)
 }
 
+/// How image pixels are sampled when [`Blitter::blit_ex`] scales or rotates an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// Use the closest pixel (blocky, noisy when shrinking)
+    Nearest,
+    /// Blend the 4 closest pixels (smooth when growing)
+    Bilinear,
+    /// Average all of the pixels that fall in the cell (smooth when shrinking)
+    Box,
+}
+
+/// How colors are reduced to the palette set with [`Blitter::palette`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dither {
+    /// Use the closest palette color
+    None,
+    /// Use a 4x4 Bayer matrix pattern
+    Ordered,
+    /// Spread the error of each cell to its neighbors
+    FloydSteinberg,
+}
+
+// 4x4 Bayer matrix for ordered dithering
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
 /// An object that can draw images into a buffer
 pub struct Blitter<'a> {
     buffer: &'a mut Buffer,
     transparent: Option<RGBA>,
+    filter: Filter,
+    palette: Option<&'a [RGBA]>,
+    dither: Dither,
 }
 
 impl<'a> Blitter<'a> {
@@ -19,6 +47,9 @@ impl<'a> Blitter<'a> {
         Blitter {
             buffer,
             transparent: None,
+            filter: Filter::Nearest,
+            palette: None,
+            dither: Dither::None,
         }
     }
 
@@ -28,38 +59,74 @@ impl<'a> Blitter<'a> {
         self
     }
 
+    /// Sets the sampling used by [`Blitter::blit_ex`] when scaling or rotating, default=[`Filter::Nearest`]
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Limits the drawn colors to the given palette, default=None (all colors)
+    pub fn palette(mut self, palette: &'a [RGBA]) -> Self {
+        self.palette = Some(palette);
+        self
+    }
+
+    /// Sets the dithering used when a palette is set, default=[`Dither::None`]
+    pub fn dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+
     /// blit an image on a console
     ///
     /// x,y are the coordinate of the top left image pixel in the console
     ///
     /// image pixels using the transparent color will be ignored
     pub fn blit(&mut self, x: i32, y: i32, image: &Image) {
+        let size = image.size();
+        self.blit_rect(x, y, image, (0, 0, size.0, size.1));
+    }
+
+    /// blit a frame of a sprite sheet on a console
+    ///
+    /// x,y are the coordinate of the top left frame pixel in the console
+    ///
+    /// frame pixels using the transparent color will be ignored
+    pub fn blit_frame(&mut self, x: i32, y: i32, sheet: &SpriteSheet, frame: usize) {
+        if let Some(rect) = sheet.frame(frame) {
+            self.blit_rect(x, y, sheet.image(), rect);
+        }
+    }
+
+    /// blit the (x, y, width, height) area of the image with the top left at x,y
+    fn blit_rect(&mut self, x: i32, y: i32, image: &Image, rect: (u32, u32, u32, u32)) {
         let buf_size = self.buffer.size();
 
         let img = image.img();
-        let width = img.width() as i32;
-        let height = img.height() as i32;
+        let (sx, sy, width, height) = rect;
+        let width = width.min(img.width().saturating_sub(sx)) as i32;
+        let height = height.min(img.height().saturating_sub(sy)) as i32;
         let minx = x.max(0);
         let miny = y.max(0);
         let maxx = (x + width).min(buf_size.0 as i32);
         let maxy = (y + height).min(buf_size.1 as i32);
         let offx = if x < 0 { -x } else { 0 };
         let offy = if y < 0 { -y } else { 0 };
-        let con_width = self.buffer.pot_size().0;
-        let back = self.buffer.backgrounds_mut();
-        for cx in minx..maxx {
-            for cy in miny..maxy {
-                let pixel = img.get_pixel((cx - minx + offx) as u32, (cy - miny + offy) as u32);
+        let mut colors = Vec::new();
+        for cy in miny..maxy {
+            for cx in minx..maxx {
+                let pixel = img.get_pixel(
+                    sx + (cx - minx + offx) as u32,
+                    sy + (cy - miny + offy) as u32,
+                );
                 let color = RGBA::rgba(pixel[0], pixel[1], pixel[2], pixel[3]);
-                if let Some(ref t) = self.transparent {
-                    if color == *t {
-                        continue;
-                    }
+                match self.transparent {
+                    Some(t) if t == color => colors.push(None),
+                    _ => colors.push(Some(color)),
                 }
-                let offset = (cx as u32 + cy as u32 * con_width) as usize;
-                back[offset] = color;
             }
         }
+        self.put_colors(minx, miny, (maxx - minx).max(0) as usize, colors);
     }
 
     /// blit an image on a console
@@ -68,16 +135,57 @@ impl<'a> Blitter<'a> {
     /// image can be scaled and rotated (angle is in radians)
     /// image pixels using the transparent color will be ignored
     pub fn blit_ex(&mut self, x: f32, y: f32, scalex: f32, scaley: f32, angle: f32, image: &Image) {
+        let size = image.size();
+        self.blit_rect_ex(x, y, scalex, scaley, angle, image, (0, 0, size.0, size.1));
+    }
+
+    /// blit a frame of a sprite sheet on a console
+    ///
+    /// x,y are the coordinate of the frame center in the console
+    /// the frame can be scaled and rotated (angle is in radians)
+    /// frame pixels using the transparent color will be ignored
+    #[allow(clippy::too_many_arguments)]
+    pub fn blit_frame_ex(
+        &mut self,
+        x: f32,
+        y: f32,
+        scalex: f32,
+        scaley: f32,
+        angle: f32,
+        sheet: &SpriteSheet,
+        frame: usize,
+    ) {
+        if let Some(rect) = sheet.frame(frame) {
+            self.blit_rect_ex(x, y, scalex, scaley, angle, sheet.image(), rect);
+        }
+    }
+
+    /// blit_ex of the (x, y, width, height) area of the image
+    #[allow(clippy::too_many_arguments)]
+    fn blit_rect_ex(
+        &mut self,
+        x: f32,
+        y: f32,
+        scalex: f32,
+        scaley: f32,
+        angle: f32,
+        image: &Image,
+        rect: (u32, u32, u32, u32),
+    ) {
         if scalex == 0.0 || scaley == 0.0 {
             return;
         }
-        let size = image.size();
+        let (sx, sy) = (rect.0, rect.1);
+        let size = (
+            rect.2.min(image.width().saturating_sub(sx)),
+            rect.3.min(image.height().saturating_sub(sy)),
+        );
         let rx = x - size.0 as f32 * 0.5;
         let ry = y - size.1 as f32 * 0.5;
         if scalex == 1.0 && scaley == 1.0 && angle == 0.0 && rx.floor() == rx && ry.floor() == ry {
             let ix = rx as i32;
             let iy = ry as i32;
-            self.blit(ix, iy, image);
+            self.blit_rect(ix, iy, image, rect);
             return;
         }
 
@@ -113,35 +221,349 @@ impl<'a> Blitter<'a> {
         let maxy = (ry + rh).min(self.buffer.height() as i32);
         let invscalex = 1.0 / scalex;
         let invscaley = 1.0 / scaley;
-        let con_width = self.buffer.pot_size().0;
-        let back = self.buffer.backgrounds_mut();
         let img = image.img();
-        for cx in minx..maxx {
-            for cy in miny..maxy {
+        let mut colors = Vec::new();
+        for cy in miny..maxy {
+            for cx in minx..maxx {
                 // map the console pixel to the image world
                 let ix = (iw + (cx as f32 - x) * newx_x + (cy as f32 - y) * (-newy_x)) * invscalex;
                 let iy = (ih + (cx as f32 - x) * (newx_y) - (cy as f32 - y) * newy_y) * invscaley;
-                let color = if ix as i32 >= size.0 as i32
+                let outside = ix as i32 >= size.0 as i32
                     || ix < 0.0
                     || iy as i32 >= size.1 as i32
-                    || iy < 0.0
-                {
+                    || iy < 0.0;
+                let color = if outside {
                     RGBA::rgba(0, 0, 0, 255)
                 } else {
-                    let pixel = img.get_pixel(ix as u32, iy as u32);
+                    let pixel = img.get_pixel(sx + ix as u32, sy + iy as u32);
                     RGBA::rgba(pixel[0], pixel[1], pixel[2], pixel[3])
                 };
-                if let Some(ref t) = self.transparent {
-                    if color == *t {
-                        continue;
-                    }
+                // filters sample around the center of the console cell
+                let (mx, my) = (cx as f32 + 0.5 - x, cy as f32 + 0.5 - y);
+                let center = (
+                    (iw + mx * newx_x + my * (-newy_x)) * invscalex,
+                    (ih + mx * newx_y - my * newy_y) * invscaley,
+                );
+                // None = transparent, the filters skip transparent pixels and are None if they are all transparent
+                let color = match self.filter {
+                    Filter::Nearest => Some(color),
+                    _ if outside => Some(color),
+                    Filter::Bilinear => self.sample_bilinear(image, rect, size, center),
+                    Filter::Box => self.sample_box(
+                        image,
+                        rect,
+                        size,
+                        center,
+                        (invscalex.abs() * 0.5, invscaley.abs() * 0.5),
+                    ),
+                };
+                colors.push(color.filter(|color| self.transparent != Some(*color)));
+            }
+        }
+        self.put_colors(minx, miny, (maxx - minx).max(0) as usize, colors);
+    }
+
+    /// Returns the image pixel at x,y (relative to the rect), None if transparent
+    fn pixel(&self, image: &Image, rect: (u32, u32, u32, u32), x: u32, y: u32) -> Option<RGBA> {
+        let pixel = image.img().get_pixel(rect.0 + x, rect.1 + y);
+        let color = RGBA::rgba(pixel[0], pixel[1], pixel[2], pixel[3]);
+        match self.transparent {
+            Some(t) if t == color => None,
+            _ => Some(color),
+        }
+    }
+
+    /// Blends the 4 pixels around ix,iy, ignoring transparent ones
+    fn sample_bilinear(
+        &self,
+        image: &Image,
+        rect: (u32, u32, u32, u32),
+        size: (u32, u32),
+        (ix, iy): (f32, f32),
+    ) -> Option<RGBA> {
+        let u = (ix - 0.5).max(0.0);
+        let v = (iy - 0.5).max(0.0);
+        let x0 = (u.floor() as u32).min(size.0 - 1);
+        let y0 = (v.floor() as u32).min(size.1 - 1);
+        let x1 = (x0 + 1).min(size.0 - 1);
+        let y1 = (y0 + 1).min(size.1 - 1);
+        let fx = u - u.floor();
+        let fy = v - v.floor();
+
+        let mut samples = [(0.0, None); 4];
+        samples[0] = ((1.0 - fx) * (1.0 - fy), self.pixel(image, rect, x0, y0));
+        samples[1] = (fx * (1.0 - fy), self.pixel(image, rect, x1, y0));
+        samples[2] = ((1.0 - fx) * fy, self.pixel(image, rect, x0, y1));
+        samples[3] = (fx * fy, self.pixel(image, rect, x1, y1));
+        weighted_average(&samples)
+    }
+
+    /// Averages the pixels within half_size of ix,iy, ignoring transparent ones
+    fn sample_box(
+        &self,
+        image: &Image,
+        rect: (u32, u32, u32, u32),
+        size: (u32, u32),
+        (ix, iy): (f32, f32),
+        half_size: (f32, f32),
+    ) -> Option<RGBA> {
+        let hx = half_size.0.max(0.5);
+        let hy = half_size.1.max(0.5);
+        let left = ((ix - hx).floor().max(0.0) as u32).min(size.0 - 1);
+        let right = ((ix + hx).ceil().max(1.0) as u32).min(size.0);
+        let top = ((iy - hy).floor().max(0.0) as u32).min(size.1 - 1);
+        let bottom = ((iy + hy).ceil().max(1.0) as u32).min(size.1);
+
+        let mut samples = Vec::new();
+        for py in top..bottom.max(top + 1) {
+            for px in left..right.max(left + 1) {
+                samples.push((1.0, self.pixel(image, rect, px, py)));
+            }
+        }
+        weighted_average(&samples)
+    }
+
+    /// Writes the colors (row major, width per row, None = skip) into the bg starting at minx,miny, applying any palette
+    fn put_colors(&mut self, minx: i32, miny: i32, width: usize, colors: Vec<Option<RGBA>>) {
+        if width == 0 {
+            return;
+        }
+        let colors = match self.palette {
+            None => colors,
+            Some(palette) => match self.dither {
+                Dither::None => colors
+                    .iter()
+                    .map(|c| c.map(|c| closest_color(palette, c)))
+                    .collect(),
+                Dither::Ordered => dither_ordered(palette, minx, miny, width, colors),
+                Dither::FloydSteinberg => dither_floyd_steinberg(palette, width, colors),
+            },
+        };
+
+        let con_width = self.buffer.pot_size().0 as usize;
+        let back = self.buffer.backgrounds_mut();
+        for (i, color) in colors.into_iter().enumerate() {
+            if let Some(color) = color {
+                let cx = minx as usize + i % width;
+                let cy = miny as usize + i / width;
+                back[cx + cy * con_width] = color;
+            }
+        }
+    }
+}
+
+/// Averages the (weight, color) samples, skipping the None ones
+fn weighted_average(samples: &[(f32, Option<RGBA>)]) -> Option<RGBA> {
+    let mut total = [0.0; 4];
+    let mut weight = 0.0;
+    for (w, color) in samples.iter() {
+        if let Some(c) = color {
+            total[0] += w * c.0 as f32;
+            total[1] += w * c.1 as f32;
+            total[2] += w * c.2 as f32;
+            total[3] += w * c.3 as f32;
+            weight += w;
+        }
+    }
+    if weight <= 0.0 {
+        return None;
+    }
+    Some(RGBA::rgba(
+        (total[0] / weight).round() as u8,
+        (total[1] / weight).round() as u8,
+        (total[2] / weight).round() as u8,
+        (total[3] / weight).round() as u8,
+    ))
+}
+
+/// Returns the palette color that is closest to the given color
+fn closest_color(palette: &[RGBA], color: RGBA) -> RGBA {
+    palette
+        .iter()
+        .min_by_key(|p| RGBA::distance(**p, color))
+        .copied()
+        .unwrap_or(color)
+}
+
+fn dither_ordered(
+    palette: &[RGBA],
+    minx: i32,
+    miny: i32,
+    width: usize,
+    colors: Vec<Option<RGBA>>,
+) -> Vec<Option<RGBA>> {
+    // about the distance between palette levels in each component
+    let spread = 255.0 / (palette.len().max(1) as f32).cbrt();
+    colors
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            c.map(|c| {
+                let cx = (minx as usize + i % width) % 4;
+                let cy = (miny as usize + i / width) % 4;
+                let offset = ((BAYER_4X4[cy][cx] as f32 + 0.5) / 16.0 - 0.5) * spread;
+                let adjusted = RGBA::rgba(
+                    (c.0 as f32 + offset).clamp(0.0, 255.0) as u8,
+                    (c.1 as f32 + offset).clamp(0.0, 255.0) as u8,
+                    (c.2 as f32 + offset).clamp(0.0, 255.0) as u8,
+                    c.3,
+                );
+                closest_color(palette, adjusted)
+            })
+        })
+        .collect()
+}
+
+fn dither_floyd_steinberg(
+    palette: &[RGBA],
+    width: usize,
+    colors: Vec<Option<RGBA>>,
+) -> Vec<Option<RGBA>> {
+    let mut errors = vec![[0.0f32; 3]; colors.len()];
+    let mut output = Vec::with_capacity(colors.len());
+    for (i, color) in colors.iter().enumerate() {
+        let c = match color {
+            None => {
+                output.push(None);
+                continue;
+            }
+            Some(c) => c,
+        };
+        let e = errors[i];
+        let wanted = [
+            (c.0 as f32 + e[0]).clamp(0.0, 255.0),
+            (c.1 as f32 + e[1]).clamp(0.0, 255.0),
+            (c.2 as f32 + e[2]).clamp(0.0, 255.0),
+        ];
+        let found = closest_color(
+            palette,
+            RGBA::rgba(wanted[0] as u8, wanted[1] as u8, wanted[2] as u8, c.3),
+        );
+        output.push(Some(found));
+
+        let diff = [
+            wanted[0] - found.0 as f32,
+            wanted[1] - found.1 as f32,
+            wanted[2] - found.2 as f32,
+        ];
+        let x = i % width;
+        let mut spread = |idx: usize, pct: f32| {
+            if let Some(err) = errors.get_mut(idx) {
+                for c in 0..3 {
+                    err[c] += diff[c] * pct;
                 }
-                let offset = (cx as u32 + cy as u32 * con_width) as usize;
-                if scalex < 1.0 || scaley < 1.0 {
-                    // todo mipmap
+            }
+        };
+        if x + 1 < width {
+            spread(i + 1, 7.0 / 16.0);
+            spread(i + width + 1, 1.0 / 16.0);
+        }
+        if x > 0 {
+            spread(i + width - 1, 3.0 / 16.0);
+        }
+        spread(i + width, 5.0 / 16.0);
+    }
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::rc::Rc;
+
+    const RED: RGBA = RGBA::rgb(255, 0, 0);
+    const BLUE: RGBA = RGBA::rgb(0, 0, 255);
+    const BLACK: RGBA = RGBA::rgb(0, 0, 0);
+
+    #[test]
+    fn blit_frame() {
+        // 2 frames of 2x1 - [RED, BLACK] and [BLUE, BLUE]
+        let mut img = Image::empty(4, 1);
+        img.put_pixel(0, 0, RED);
+        img.put_pixel(1, 0, BLACK);
+        img.put_pixel(2, 0, BLUE);
+        img.put_pixel(3, 0, BLUE);
+        let sheet = SpriteSheet::with_grid(Rc::new(img), 2, 1);
+
+        let mut buffer = Buffer::new(10, 10);
+        image(&mut buffer)
+            .transparent(BLACK)
+            .blit_frame(1, 1, &sheet, 0);
+        assert_eq!(buffer.get_back(1, 1), Some(&RED));
+        assert_eq!(buffer.get_back(2, 1), Some(&RGBA::new()));
+        assert_eq!(buffer.get_back(3, 1), Some(&RGBA::new()));
+
+        image(&mut buffer).blit_frame_ex(5.0, 5.5, 1.0, 1.0, 0.0, &sheet, 1);
+        assert_eq!(buffer.get_back(4, 5), Some(&BLUE));
+        assert_eq!(buffer.get_back(5, 5), Some(&BLUE));
+        assert_eq!(buffer.get_back(6, 5), Some(&RGBA::new()));
+    }
+
+    #[test]
+    fn box_filter() {
+        // left half red, right half blue
+        let mut img = Image::empty(4, 4);
+        for x in 0..4 {
+            for y in 0..4 {
+                img.put_pixel(x, y, if x < 2 { RED } else { BLUE });
+            }
+        }
+
+        let mut buffer = Buffer::new(10, 10);
+        image(&mut buffer).blit_ex(5.5, 5.5, 0.25, 0.25, 0.0, &img);
+        assert_eq!(buffer.get_back(5, 5), Some(&RED));
+
+        image(&mut buffer)
+            .filter(Filter::Box)
+            .blit_ex(5.5, 5.5, 0.25, 0.25, 0.0, &img);
+        assert_eq!(buffer.get_back(5, 5), Some(&RGBA::rgb(128, 0, 128)));
+
+        // the key color is left out of the filtered sample, not tested on the nearest pixel
+        let mut buffer = Buffer::new(10, 10);
+        image(&mut buffer)
+            .filter(Filter::Box)
+            .transparent(RED)
+            .blit_ex(5.5, 5.5, 0.25, 0.25, 0.0, &img);
+        assert_eq!(buffer.get_back(5, 5), Some(&BLUE));
+    }
+
+    #[test]
+    fn dither_palette() {
+        let grey = RGBA::rgb(128, 128, 128);
+        let white = RGBA::rgb(255, 255, 255);
+        let palette = [BLACK, white];
+        let mut img = Image::empty(4, 4);
+        for x in 0..4 {
+            for y in 0..4 {
+                img.put_pixel(x, y, grey);
+            }
+        }
+
+        let count_white = |buffer: &Buffer| {
+            let mut count = 0;
+            for x in 0..4 {
+                for y in 0..4 {
+                    let back = buffer.get_back(x, y).unwrap();
+                    assert!(*back == BLACK || *back == white);
+                    if *back == white {
+                        count += 1;
+                    }
                 }
-                back[offset] = color;
             }
+            count
+        };
+
+        let mut buffer = Buffer::new(10, 10);
+        image(&mut buffer).palette(&palette).blit(0, 0, &img);
+        assert_eq!(count_white(&buffer), 16);
+
+        for dither in [Dither::Ordered, Dither::FloydSteinberg] {
+            let mut buffer = Buffer::new(10, 10);
+            image(&mut buffer)
+                .palette(&palette)
+                .dither(dither)
+                .blit(0, 0, &img);
+            assert_eq!(count_white(&buffer), 8);
         }
     }
 }
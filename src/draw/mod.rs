@@ -18,3 +18,9 @@ pub use self::image::*;
 
 mod subcell;
 pub use subcell::*;
+
+mod pixel;
+pub use pixel::*;
+
+mod ascii;
+pub use ascii::*;
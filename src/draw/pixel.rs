@@ -0,0 +1,390 @@
+use crate::simple::{Buffer, Glyph};
+use crate::Image;
+use crate::RGBA;
+
+// high resolution pixel modes that work with regular (CP437 or unicode) fonts
+
+/// Constructs a new [`HalfBlock`] drawer for this buffer (1x2 pixels per cell)
+pub fn halfblock(buffer: &mut Buffer) -> HalfBlock<'_> {
+    HalfBlock::new(buffer)
+}
+
+/// Constructs a new [`Braille`] drawer for this buffer (2x4 pixels per cell)
+pub fn braille(buffer: &mut Buffer) -> Braille<'_> {
+    Braille::new(buffer)
+}
+
+/// The source of pixels for a blit - a sub rect of an image or a raw grid of colors
+struct PixelSource<'b> {
+    pixels: &'b dyn Fn(u32, u32) -> RGBA,
+    left: i32,
+    top: i32,
+    width: i32,
+    height: i32,
+    transparent: Option<RGBA>,
+}
+
+impl<'b> PixelSource<'b> {
+    /// Returns the pixel at the x,y offset from the top left of the source, None if outside or transparent
+    fn get(&self, x: i32, y: i32) -> Option<RGBA> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        let color = (self.pixels)((self.left + x) as u32, (self.top + y) as u32);
+        match self.transparent {
+            Some(t) if t == color => None,
+            _ => Some(color),
+        }
+    }
+}
+
+fn image_source<'b>(
+    img: &'b Image,
+    sx: i32,
+    sy: i32,
+    w: Option<i32>,
+    h: Option<i32>,
+    pixels: &'b dyn Fn(u32, u32) -> RGBA,
+    transparent: Option<RGBA>,
+) -> PixelSource<'b> {
+    let left = sx.max(0);
+    let top = sy.max(0);
+    let width = w
+        .unwrap_or(img.width() as i32)
+        .min(img.width() as i32 - left);
+    let height = h
+        .unwrap_or(img.height() as i32)
+        .min(img.height() as i32 - top);
+    PixelSource {
+        pixels,
+        left,
+        top,
+        width,
+        height,
+        transparent,
+    }
+}
+
+/// Draws images using the half block glyphs ('▀' and '▄') for twice the vertical resolution
+///
+/// Each cell shows 2 pixels, the top one in the fg and the bottom one in the bg.
+/// Both glyphs are in CP437, so this works with most fonts.
+pub struct HalfBlock<'a> {
+    buffer: &'a mut Buffer,
+    transparent: Option<RGBA>,
+    to_glyph: Option<&'a dyn Fn(char) -> Glyph>,
+}
+
+impl<'a> HalfBlock<'a> {
+    /// Constructs a new HalfBlock drawer for this buffer
+    pub fn new(buffer: &'a mut Buffer) -> Self {
+        HalfBlock {
+            buffer,
+            transparent: None,
+            to_glyph: None,
+        }
+    }
+
+    /// Sets the color of the image that should be transparent when blitted, default=None
+    pub fn transparent(mut self, color: RGBA) -> Self {
+        self.transparent = Some(color);
+        self
+    }
+
+    /// Sets the char->Glyph conversion function, default=the buffer's codepage (from the console's font) or (ch as u32)
+    ///
+    /// Use [`crate::codepage437::to_glyph`] with CP437 fonts.
+    pub fn to_glyph(mut self, to_glyph: &'a dyn Fn(char) -> Glyph) -> Self {
+        self.to_glyph = Some(to_glyph);
+        self
+    }
+
+    /// blit a part of an image on the console at dx,dy (in cells)
+    ///
+    /// sx,sy,w,h is the area of the image to use (in pixels), default=the whole image
+    #[allow(clippy::too_many_arguments)]
+    pub fn blit(
+        &mut self,
+        img: &Image,
+        dx: i32,
+        dy: i32,
+        sx: i32,
+        sy: i32,
+        w: Option<i32>,
+        h: Option<i32>,
+    ) {
+        let pixels = |x, y| {
+            let p = img.img().get_pixel(x, y);
+            RGBA::rgba(p[0], p[1], p[2], p[3])
+        };
+        let source = image_source(img, sx, sy, w, h, &pixels, self.transparent);
+        self.draw(&source, dx, dy);
+    }
+
+    /// blit a raw grid of pixels (row major, `width` pixels per row) on the console at dx,dy (in cells)
+    pub fn blit_grid(&mut self, pixels: &[RGBA], width: usize, dx: i32, dy: i32) {
+        if width == 0 {
+            return;
+        }
+        let get = |x, y| pixels[x as usize + y as usize * width];
+        let source = PixelSource {
+            pixels: &get,
+            left: 0,
+            top: 0,
+            width: width as i32,
+            height: (pixels.len() / width) as i32,
+            transparent: self.transparent,
+        };
+        self.draw(&source, dx, dy);
+    }
+
+    fn draw(&mut self, source: &PixelSource, dx: i32, dy: i32) {
+        let upper = self.buffer.glyph_with(self.to_glyph, '▀');
+        let lower = self.buffer.glyph_with(self.to_glyph, '▄');
+        let space = self.buffer.glyph_with(self.to_glyph, ' ');
+
+        for cy in 0..(source.height + 1) / 2 {
+            for cx in 0..source.width {
+                let top = source.get(cx, cy * 2);
+                let bottom = source.get(cx, cy * 2 + 1);
+                let (x, y) = (dx + cx, dy + cy);
+                match (top, bottom) {
+                    (None, None) => {}
+                    (Some(top), None) => self.buffer.draw_opt(x, y, Some(upper), Some(top), None),
+                    (None, Some(bottom)) => {
+                        self.buffer.draw_opt(x, y, Some(lower), Some(bottom), None)
+                    }
+                    (Some(top), Some(bottom)) if top == bottom => {
+                        self.buffer.draw_opt(x, y, Some(space), None, Some(top))
+                    }
+                    (Some(top), Some(bottom)) => {
+                        self.buffer.draw(x, y, upper, top, bottom);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Draws monochrome images and plots using the braille glyphs (U+2800-U+28FF)
+///
+/// Each cell shows 2x4 dots.  A pixel is drawn as a dot if it is not transparent and its luminance is >= the threshold.
+/// Braille glyphs are not in CP437, so this needs a unicode font.
+pub struct Braille<'a> {
+    buffer: &'a mut Buffer,
+    fg: Option<RGBA>,
+    bg: Option<RGBA>,
+    transparent: Option<RGBA>,
+    threshold: u8,
+    to_glyph: Option<&'a dyn Fn(char) -> Glyph>,
+}
+
+// dot bits for the pixels in a cell - [y][x]
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+impl<'a> Braille<'a> {
+    /// Constructs a new Braille drawer for this buffer
+    pub fn new(buffer: &'a mut Buffer) -> Self {
+        Braille {
+            buffer,
+            fg: None,
+            bg: None,
+            transparent: None,
+            threshold: 128,
+            to_glyph: None,
+        }
+    }
+
+    /// Sets the fg color of the dots, default=average color of the dots in each cell
+    pub fn fg(mut self, fg: RGBA) -> Self {
+        self.fg = Some(fg);
+        self
+    }
+
+    /// Sets the bg color of the cells, default=None
+    pub fn bg(mut self, bg: RGBA) -> Self {
+        self.bg = Some(bg);
+        self
+    }
+
+    /// Sets the color of the image that should be transparent when blitted, default=None
+    pub fn transparent(mut self, color: RGBA) -> Self {
+        self.transparent = Some(color);
+        self
+    }
+
+    /// Sets the luminance a pixel must have to be drawn as a dot, default=128
+    pub fn threshold(mut self, threshold: u8) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the char->Glyph conversion function, default=the buffer's codepage (from the console's font) or (ch as u32)
+    pub fn to_glyph(mut self, to_glyph: &'a dyn Fn(char) -> Glyph) -> Self {
+        self.to_glyph = Some(to_glyph);
+        self
+    }
+
+    /// blit a part of an image on the console at dx,dy (in cells)
+    ///
+    /// sx,sy,w,h is the area of the image to use (in pixels), default=the whole image
+    #[allow(clippy::too_many_arguments)]
+    pub fn blit(
+        &mut self,
+        img: &Image,
+        dx: i32,
+        dy: i32,
+        sx: i32,
+        sy: i32,
+        w: Option<i32>,
+        h: Option<i32>,
+    ) {
+        let pixels = |x, y| {
+            let p = img.img().get_pixel(x, y);
+            RGBA::rgba(p[0], p[1], p[2], p[3])
+        };
+        let source = image_source(img, sx, sy, w, h, &pixels, self.transparent);
+        self.draw(&source, dx, dy);
+    }
+
+    /// blit a raw grid of pixels (row major, `width` pixels per row) on the console at dx,dy (in cells)
+    pub fn blit_grid(&mut self, pixels: &[RGBA], width: usize, dx: i32, dy: i32) {
+        if width == 0 {
+            return;
+        }
+        let get = |x, y| pixels[x as usize + y as usize * width];
+        let source = PixelSource {
+            pixels: &get,
+            left: 0,
+            top: 0,
+            width: width as i32,
+            height: (pixels.len() / width) as i32,
+            transparent: self.transparent,
+        };
+        self.draw(&source, dx, dy);
+    }
+
+    /// plot a raw grid of dots (row major, `width` dots per row) on the console at dx,dy (in cells)
+    pub fn plot(&mut self, dots: &[bool], width: usize, dx: i32, dy: i32) {
+        if width == 0 {
+            return;
+        }
+        let fg = self.fg.unwrap_or(RGBA::rgb(255, 255, 255));
+        let get = |x, y| match dots[x as usize + y as usize * width] {
+            true => fg,
+            false => RGBA::new(),
+        };
+        let source = PixelSource {
+            pixels: &get,
+            left: 0,
+            top: 0,
+            width: width as i32,
+            height: (dots.len() / width) as i32,
+            transparent: Some(RGBA::new()),
+        };
+        self.draw(&source, dx, dy);
+    }
+
+    fn draw(&mut self, source: &PixelSource, dx: i32, dy: i32) {
+        for cy in 0..(source.height + 3) / 4 {
+            for cx in 0..(source.width + 1) / 2 {
+                let mut bits = 0;
+                let mut total = (0u32, 0u32, 0u32, 0u32);
+                for (py, row) in BRAILLE_DOTS.iter().enumerate() {
+                    for (px, bit) in row.iter().enumerate() {
+                        let color = match source.get(cx * 2 + px as i32, cy * 4 + py as i32) {
+                            None => continue,
+                            Some(color) => color,
+                        };
+                        if color.luminance() < self.threshold {
+                            continue;
+                        }
+                        bits |= bit;
+                        total.0 += color.0 as u32;
+                        total.1 += color.1 as u32;
+                        total.2 += color.2 as u32;
+                        total.3 += 1;
+                    }
+                }
+
+                let fg = match (self.fg, total.3) {
+                    (Some(fg), _) => Some(fg),
+                    (None, 0) => None,
+                    (None, n) => Some(RGBA::rgb(
+                        (total.0 / n) as u8,
+                        (total.1 / n) as u8,
+                        (total.2 / n) as u8,
+                    )),
+                };
+                let ch = char::from_u32(0x2800 + bits).unwrap_or(' ');
+                let glyph = self.buffer.glyph_with(self.to_glyph, ch);
+                self.buffer
+                    .draw_opt(dx + cx, dy + cy, Some(glyph), fg, self.bg);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RED: RGBA = RGBA::rgb(255, 0, 0);
+    const BLUE: RGBA = RGBA::rgb(0, 0, 255);
+    const WHITE: RGBA = RGBA::rgb(255, 255, 255);
+
+    #[test]
+    fn halfblock_grid() {
+        let mut buffer = Buffer::new(10, 10);
+        let pixels = [RED, RED, BLUE, RED];
+        halfblock(&mut buffer).blit_grid(&pixels, 2, 1, 1);
+
+        assert_eq!(buffer.get_glyph(1, 1), Some(&('▀' as u32)));
+        assert_eq!(buffer.get_fore(1, 1), Some(&RED));
+        assert_eq!(buffer.get_back(1, 1), Some(&BLUE));
+
+        assert_eq!(buffer.get_glyph(2, 1), Some(&(' ' as u32)));
+        assert_eq!(buffer.get_back(2, 1), Some(&RED));
+    }
+
+    #[test]
+    fn halfblock_transparent() {
+        let mut buffer = Buffer::new(10, 10);
+        let pixels = [BLUE, RED];
+        halfblock(&mut buffer)
+            .transparent(BLUE)
+            .blit_grid(&pixels, 1, 0, 0);
+
+        assert_eq!(buffer.get_glyph(0, 0), Some(&('▄' as u32)));
+        assert_eq!(buffer.get_fore(0, 0), Some(&RED));
+        assert_eq!(buffer.get_back(0, 0), Some(&RGBA::new()));
+    }
+
+    #[test]
+    fn braille_plot() {
+        let mut buffer = Buffer::new(10, 10);
+        #[rustfmt::skip]
+        let dots = [
+            true, false, false,
+            false, true, false,
+            false, false, false,
+            true, true, true,
+        ];
+        braille(&mut buffer).fg(WHITE).plot(&dots, 3, 0, 0);
+
+        assert_eq!(buffer.get_glyph(0, 0), Some(&0x28D1));
+        assert_eq!(buffer.get_glyph(1, 0), Some(&0x2840));
+        assert_eq!(buffer.get_fore(0, 0), Some(&WHITE));
+    }
+
+    #[test]
+    fn braille_average_color() {
+        let mut buffer = Buffer::new(10, 10);
+        let pixels = [WHITE, RGBA::rgb(155, 155, 155), BLUE, RED];
+        braille(&mut buffer).blit_grid(&pixels, 2, 0, 0);
+
+        // BLUE is too dark to be a dot
+        assert_eq!(buffer.get_glyph(0, 0), Some(&0x2809));
+        assert_eq!(buffer.get_fore(0, 0), Some(&RGBA::rgb(205, 205, 205)));
+    }
+}
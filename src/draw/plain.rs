@@ -1,5 +1,7 @@
 use crate::simple::{Buffer, Glyph};
-use crate::text::{wrap_plain, RefLine};
+use crate::text::{
+    display_width, fix_wide_halves, text_cells, wrap_hyphenated, wrap_plain, Hyphenator, RefLine,
+};
 use crate::RGBA;
 use std::cmp::{max, min};
 
@@ -8,6 +10,115 @@ pub enum TextAlign {
     Left,
     Right,
     Center,
+    /// Spreads the words of wrapped lines to fill the width, the last line of each paragraph is left aligned
+    Justify,
+}
+
+/// Where the lines go in the height of the printing
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VerticalAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl VerticalAlign {
+    /// The number of rows to skip before printing this many lines in the height
+    pub(crate) fn offset(&self, lines: i32, height: Option<i32>) -> i32 {
+        let spare = match height {
+            None => return 0,
+            Some(height) => (height - lines).max(0),
+        };
+        match self {
+            VerticalAlign::Top => 0,
+            VerticalAlign::Middle => spare / 2,
+            VerticalAlign::Bottom => spare,
+        }
+    }
+}
+
+/// What to do with text that does not fit in the width (or height when wrapping)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Cut the text at the width
+    Clip,
+    /// Cut the text and end it with the ellipsis
+    Ellipsis,
+    /// Keep the start and the end of the text with the ellipsis between them (good for file paths)
+    ///
+    /// When wrapping text that does not fit the height, the last line ends with the ellipsis.
+    MiddleEllipsis,
+    /// Cut the text and fade out the last few cells using the fg alpha
+    Fade,
+}
+
+// The number of cells that get faded with Overflow::Fade
+const FADE_LEN: usize = 3;
+
+impl Overflow {
+    /// Fits the items into the width - more means there is text after these items (e.g. lines cut by the height)
+    ///
+    /// The ellipsis is made from the last item that is kept (if any).
+    /// Returns the items to draw and the number of items at the end to fade.
+    pub(crate) fn apply<T: Clone>(
+        &self,
+        items: &[T],
+        width: usize,
+        more: bool,
+        ellipsis: impl Fn(Option<&T>) -> Vec<T>,
+    ) -> (Vec<T>, usize) {
+        let too_long = items.len() > width;
+        if !too_long && !more {
+            return (items.to_vec(), 0);
+        }
+        match self {
+            Overflow::Clip => (items[..min(width, items.len())].to_vec(), 0),
+            Overflow::Fade => {
+                let out = items[..min(width, items.len())].to_vec();
+                let fade = min(FADE_LEN, out.len());
+                (out, fade)
+            }
+            Overflow::MiddleEllipsis if !more => {
+                let dots = ellipsis(items.first());
+                let dots_len = min(dots.len(), width);
+                let avail = width - dots_len;
+                let head = avail.div_ceil(2);
+                let tail = avail / 2;
+
+                let mut out = items[..head].to_vec();
+                out.extend(dots.into_iter().take(dots_len));
+                out.extend_from_slice(&items[items.len() - tail..]);
+                (out, 0)
+            }
+            Overflow::Ellipsis | Overflow::MiddleEllipsis => {
+                let dots_len = ellipsis(items.first()).len();
+                let head = match items.len() + dots_len <= width {
+                    true => items.len(),
+                    false => width.saturating_sub(dots_len),
+                };
+                let mut out = items[..head].to_vec();
+                out.extend(ellipsis(out.last()));
+                out.truncate(width);
+                (out, 0)
+            }
+        }
+    }
+}
+
+/// The fg to use for a cell that is the given number of cells from the end of a faded line
+pub(crate) fn fade_fg(fg: Option<RGBA>, from_end: usize) -> Option<RGBA> {
+    fg.map(|fg| {
+        let alpha = fg.3 as usize * (from_end + 1) / (FADE_LEN + 1);
+        RGBA::rgba(fg.0, fg.1, fg.2, alpha as u8)
+    })
+}
+
+/// The extra spaces to add at the given gap, to spread the extra cells over all of the gaps
+pub(crate) fn justify_spaces(extra: i32, gaps: i32, gap: i32) -> i32 {
+    if gaps <= 0 || extra <= 0 {
+        return 0;
+    }
+    extra / gaps + if gap < extra % gaps { 1 } else { 0 }
 }
 
 pub fn plain<'a>(buffer: &'a mut Buffer) -> PlainPrinter {
@@ -23,9 +134,14 @@ pub struct PlainPrinter<'a> {
     width: Option<i32>,
     height: Option<i32>,
     align: TextAlign,
+    valign: VerticalAlign,
+    overflow: Overflow,
+    ellipsis: &'a str,
     fg: Option<RGBA>,
     bg: Option<RGBA>,
-    to_glyph: &'a dyn Fn(char) -> Glyph,
+    font: Option<u8>,
+    to_glyph: Option<&'a dyn Fn(char) -> Glyph>,
+    hyphenator: Option<&'a Hyphenator>,
 }
 
 impl<'a> PlainPrinter<'a> {
@@ -35,9 +151,14 @@ impl<'a> PlainPrinter<'a> {
             width: None,
             height: None,
             align: TextAlign::Left,
+            valign: VerticalAlign::Top,
+            overflow: Overflow::Clip,
+            ellipsis: "...",
             fg: Some(RGBA::rgb(255, 255, 255)),
             bg: None,
-            to_glyph: &|ch| ch as u32,
+            font: None,
+            to_glyph: None,
+            hyphenator: None,
         }
     }
 
@@ -56,6 +177,24 @@ impl<'a> PlainPrinter<'a> {
         self
     }
 
+    /// Sets the vertical alignment of the lines in the height (for `print_lines` and `wrap`), default=Top
+    pub fn valign(mut self, valign: VerticalAlign) -> Self {
+        self.valign = valign;
+        self
+    }
+
+    /// Sets what happens to text that does not fit the width (or the height when wrapping), default=Clip
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Sets the text used by the ellipsis overflow policies, default="..."
+    pub fn ellipsis(mut self, ellipsis: &'a str) -> Self {
+        self.ellipsis = ellipsis;
+        self
+    }
+
     pub fn fg(mut self, fg: RGBA) -> Self {
         self.fg = Some(fg);
         self
@@ -66,21 +205,30 @@ impl<'a> PlainPrinter<'a> {
         self
     }
 
+    /// Sets the font slot of the printed cells (see [`Buffer::font`]), default=None (the console's font)
+    pub fn font(mut self, font: u8) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// Sets the char->Glyph conversion function, default=the buffer's codepage (from the console's font) or (ch as u32)
     pub fn to_glyph(mut self, to_glyph: &'a dyn Fn(char) -> Glyph) -> Self {
-        self.to_glyph = to_glyph;
+        self.to_glyph = Some(to_glyph);
         self
     }
 
-    fn print_char(&mut self, x: i32, y: i32, ch: char) {
-        let glyph = (self.to_glyph)(ch);
-        self.buffer.draw_opt(x, y, Some(glyph), self.fg, self.bg);
+    /// Sets the hyphenator that `wrap` uses to break long words at syllables (see [`wrap_hyphenated`])
+    pub fn hyphenator(mut self, hyphenator: &'a Hyphenator) -> Self {
+        self.hyphenator = Some(hyphenator);
+        self
     }
 
     pub fn print(&mut self, x: i32, y: i32, text: &str) -> i32 {
-        let mut width = self.width.unwrap_or(text.chars().count() as i32);
+        let mut width = self.width.unwrap_or(display_width(text) as i32);
+        let chars = self.fit_chars(text_cells(text), width, false);
         let mut start = 0;
         let mut ix = match self.align {
-            TextAlign::Left => x,
+            TextAlign::Left | TextAlign::Justify => x,
             TextAlign::Right => x - width + 1,
             TextAlign::Center => x - width / 2,
         };
@@ -93,42 +241,96 @@ impl<'a> PlainPrinter<'a> {
             width = self.buffer.width() as i32 - ix;
         }
 
-        let w = self.print_part(ix, y, start as usize, width as usize, text);
+        let w = self.print_part(ix, y, start as usize, width as usize, &chars);
 
         if let Some(height) = self.height {
             for y1 in 1..height {
                 for x1 in 0..w {
-                    self.buffer
-                        .draw_opt(ix + x1, y + y1, Some(0), self.fg, self.bg);
+                    self.buffer.draw_opt_font(
+                        ix + x1,
+                        y + y1,
+                        Some(0),
+                        self.fg,
+                        self.bg,
+                        self.font,
+                    );
                 }
             }
         }
         w
     }
 
-    fn print_part(&mut self, x: i32, y: i32, start: usize, count: usize, text: &str) -> i32 {
-        let mut chars = text.chars().skip(start);
+    /// Draws the named glyph (see [`crate::GlyphRegistry`]) with its default colors, or the fg and bg if it has none
+    ///
+    /// Returns false (and draws nothing) if the buffer's glyph registry does not have the name.
+    pub fn print_glyph(&mut self, x: i32, y: i32, name: &str) -> bool {
+        let def = match self.buffer.glyph_def(name) {
+            None => return false,
+            Some(def) => *def,
+        };
+        self.buffer.draw_opt_font(
+            x,
+            y,
+            Some(def.glyph),
+            def.fg.or(self.fg),
+            def.bg.or(self.bg),
+            self.font,
+        );
+        true
+    }
+
+    fn print_part(
+        &mut self,
+        x: i32,
+        y: i32,
+        start: usize,
+        count: usize,
+        chars: &[(char, Glyph, Option<RGBA>)],
+    ) -> i32 {
+        let mut chars = chars.iter().skip(start);
         let mut ix = x;
         for _ in 0..count {
-            let ch = match chars.next() {
-                None => '\0',
-                Some(ch) => ch,
+            let (ch, flags, fg) = match chars.next() {
+                None => ('\0', 0, self.fg),
+                Some(item) => *item,
             };
-            self.print_char(ix, y, ch);
-            // self.buffer.draw_opt(ix, y, glyph, self.fg, self.bg);
+            let glyph = self.buffer.glyph_with(self.to_glyph, ch) | flags;
+            self.buffer
+                .draw_opt_font(ix, y, Some(glyph), fg, self.bg, self.font);
             ix += 1;
         }
         count as i32
     }
 
+    /// Applies the overflow policy to the cells (from `text_cells`), returns the chars, glyph flags and their fg
+    fn fit_chars(
+        &self,
+        chars: Vec<(char, Glyph)>,
+        width: i32,
+        more: bool,
+    ) -> Vec<(char, Glyph, Option<RGBA>)> {
+        let ellipsis = text_cells(self.ellipsis);
+        let (mut chars, fade) = self
+            .overflow
+            .apply(&chars, width.max(0) as usize, more, |_| ellipsis.clone());
+        fix_wide_halves(&mut chars, |c| c.1, |c| *c = (' ', 0));
+        let len = chars.len();
+        chars
+            .into_iter()
+            .enumerate()
+            .map(|(i, (ch, flags))| match len - i <= fade {
+                true => (ch, flags, fade_fg(self.fg, len - i - 1)),
+                false => (ch, flags, self.fg),
+            })
+            .collect()
+    }
+
     pub fn print_lines(&mut self, x: i32, y: i32, text: &str) -> (i32, i32) {
         let max_height = self.height.unwrap_or(999) as usize;
+        let count = min(text.split('\n').count(), max_height) as i32;
         let mut width = 0;
-        let mut height = 0;
-        for (i, line) in text.split('\n').enumerate() {
-            if i >= max_height {
-                break;
-            }
+        let mut height = self.valign.offset(count, self.height);
+        for line in text.split('\n').take(max_height) {
             let w = self.print(x, y + height, line);
             width = max(width, w);
             height += 1;
@@ -140,14 +342,31 @@ impl<'a> PlainPrinter<'a> {
         let width = self.width.unwrap_or(self.buffer.width() as i32 - x);
         let max_height = self.height.unwrap_or(999) as usize;
 
+        // wrap each paragraph on its own so we know which lines end them (for justify)
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let wrapped = match self.hyphenator {
+                None => wrap_plain(width as usize, paragraph),
+                Some(hyphenator) => wrap_hyphenated(width as usize, paragraph, hyphenator),
+            };
+            let count = wrapped.len();
+            lines.extend(
+                wrapped
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, l)| (l, i + 1 == count)),
+            );
+        }
+        let more = lines.len() > max_height;
+        lines.truncate(max_height);
+
         let mut widest = 0;
 
-        let mut cy = y;
-        for (i, line) in wrap_plain(width as usize, text).iter().enumerate() {
-            if i >= max_height {
-                break;
-            }
-            let w = self.print_line(x, cy, &line);
+        let count = lines.len();
+        let mut cy = y + self.valign.offset(count as i32, self.height);
+        for (i, (line, last)) in lines.iter().enumerate() {
+            let cut = more && i + 1 == count;
+            let w = self.print_line(x, cy, line, !last, cut);
             widest = max(widest, w);
             cy += 1;
         }
@@ -309,13 +528,26 @@ impl<'a> PlainPrinter<'a> {
         // (widest, cy - y + 1)
     }
 
-    fn print_line(&mut self, x: i32, y: i32, line: &RefLine) -> i32 {
-        let width = self.width.unwrap_or(line.char_len() as i32);
-        let self_len = min(width, line.char_len() as i32);
-        let spaces = width.saturating_sub(self_len);
+    /// Prints the line - more means there are more lines that did not fit the height
+    fn print_line(&mut self, x: i32, y: i32, line: &RefLine, can_justify: bool, more: bool) -> i32 {
+        let limit = self.width.unwrap_or(i32::MAX);
+        let chars = self.fit_chars(text_cells(&line.to_string()), limit, more);
+        let width = self.width.unwrap_or(chars.len() as i32);
+        let self_len = min(width, chars.len() as i32);
+        let mut spaces = width.saturating_sub(self_len);
+
+        let gaps = chars.iter().filter(|(ch, _, _)| *ch == ' ').count() as i32;
+        let (extra, gaps) = match self.align {
+            TextAlign::Justify if can_justify && !more && gaps > 0 => {
+                let extra = spaces;
+                spaces = 0;
+                (extra, gaps)
+            }
+            _ => (0, 0),
+        };
 
         let (x, pre, post) = match self.align {
-            TextAlign::Left => (x, 0, spaces),
+            TextAlign::Left | TextAlign::Justify => (x, 0, spaces),
             TextAlign::Center => {
                 let half = spaces / 2;
                 (x - half - self_len / 2, half, spaces - half)
@@ -329,24 +561,29 @@ impl<'a> PlainPrinter<'a> {
 
         // let mut output = "[".to_string();
         for _ in 0..pre {
-            self.buffer.draw_opt(cx, y, Some(0), fg, bg);
+            self.buffer.draw_opt_font(cx, y, Some(0), fg, bg, self.font);
             cx += 1;
         }
 
         // output += self.0;
-        for char in line.as_str().chars() {
-            let glyph = (self.to_glyph)(char);
-            self.buffer.draw_opt(cx, y, Some(glyph), fg, bg);
-            cx += 1;
-        }
-
-        if line.has_hyphen() {
-            self.buffer.draw_opt(cx, y, Some('-' as u32), fg, bg);
+        let mut gap = 0;
+        for (char, flags, char_fg) in chars.iter() {
+            let glyph = self.buffer.glyph_with(self.to_glyph, *char);
+            self.buffer
+                .draw_opt_font(cx, y, Some(glyph | flags), *char_fg, bg, self.font);
             cx += 1;
+            if *char == ' ' {
+                for _ in 0..justify_spaces(extra, gaps, gap) {
+                    self.buffer
+                        .draw_opt_font(cx, y, Some(glyph), fg, bg, self.font);
+                    cx += 1;
+                }
+                gap += 1;
+            }
         }
 
         for _ in 0..post {
-            self.buffer.draw_opt(cx, y, Some(0), fg, bg);
+            self.buffer.draw_opt_font(cx, y, Some(0), fg, bg, self.font);
             cx += 1;
         }
 
@@ -360,6 +597,9 @@ impl<'a> PlainPrinter<'a> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::simple::{WIDE_CONTINUATION, WIDE_GLYPH};
+    use crate::Codepage;
+    use std::rc::Rc;
 
     fn extract_line(buf: &Buffer, x: i32, y: i32, width: i32) -> String {
         let mut output = "".to_string();
@@ -509,4 +749,188 @@ mod test {
             assert_eq!(r, (15, 9));
         }
     }
+
+    #[test]
+    fn wrap_justify() {
+        let mut buffer = Buffer::new(50, 50);
+        let mut printer = plain(&mut buffer).width(12).align(TextAlign::Justify);
+
+        let r = printer.wrap(0, 1, "taco is a great deal for a bit\nfast food place");
+        assert_eq!(extract_line(&buffer, 0, 1, 13), "taco   is  a\0");
+        assert_eq!(extract_line(&buffer, 0, 2, 13), "great   deal\0");
+        assert_eq!(extract_line(&buffer, 0, 3, 13), "for a bit\0\0\0\0");
+        assert_eq!(extract_line(&buffer, 0, 4, 13), "fast    food\0");
+        assert_eq!(extract_line(&buffer, 0, 5, 13), "place\0\0\0\0\0\0\0\0");
+        assert_eq!(r, (12, 5));
+    }
+
+    #[test]
+    fn wrap_valign() {
+        let mut buffer = Buffer::new(50, 50);
+        let mut printer = plain(&mut buffer)
+            .width(10)
+            .height(5)
+            .valign(VerticalAlign::Bottom);
+
+        let r = printer.wrap(0, 0, "taco casa is a great");
+        assert_eq!(extract_line(&buffer, 0, 2, 10), "\0\0\0\0\0\0\0\0\0\0");
+        assert_eq!(extract_line(&buffer, 0, 3, 10), "taco casa\0");
+        assert_eq!(extract_line(&buffer, 0, 4, 10), "is a great");
+        assert_eq!(r, (10, 5));
+
+        let mut printer = plain(&mut buffer)
+            .width(10)
+            .height(4)
+            .valign(VerticalAlign::Middle);
+        let r = printer.wrap(20, 0, "taco casa is a great");
+        assert_eq!(extract_line(&buffer, 20, 1, 10), "taco casa\0");
+        assert_eq!(r, (10, 3));
+    }
+
+    #[test]
+    fn print_overflow() {
+        let mut buffer = Buffer::new(50, 50);
+        let text = "/home/user/games/saves/slot1.sav";
+
+        plain(&mut buffer)
+            .width(12)
+            .overflow(Overflow::Ellipsis)
+            .print(0, 0, text);
+        assert_eq!(extract_line(&buffer, 0, 0, 13), "/home/use...\0");
+
+        plain(&mut buffer)
+            .width(13)
+            .overflow(Overflow::MiddleEllipsis)
+            .print(0, 1, text);
+        assert_eq!(extract_line(&buffer, 0, 1, 14), "/home...1.sav\0");
+
+        plain(&mut buffer)
+            .width(12)
+            .overflow(Overflow::Fade)
+            .fg(RGBA::rgb(255, 255, 255))
+            .print(0, 2, text);
+        assert_eq!(extract_line(&buffer, 0, 2, 12), "/home/user/g");
+        assert_eq!(buffer.get_fore(8, 2).unwrap().3, 255);
+        assert_eq!(buffer.get_fore(9, 2).unwrap().3, 191);
+        assert_eq!(buffer.get_fore(11, 2).unwrap().3, 63);
+    }
+
+    #[test]
+    fn wrap_overflow_height() {
+        let mut buffer = Buffer::new(50, 50);
+        let r = plain(&mut buffer)
+            .width(10)
+            .height(2)
+            .overflow(Overflow::Ellipsis)
+            .wrap(0, 0, "taco casa is a great fast food place");
+        assert_eq!(extract_line(&buffer, 0, 0, 10), "taco casa\0");
+        assert_eq!(extract_line(&buffer, 0, 1, 10), "is a gr...");
+        assert_eq!(r, (10, 2));
+
+        plain(&mut buffer)
+            .width(10)
+            .height(1)
+            .overflow(Overflow::Ellipsis)
+            .wrap(0, 3, "taco cas is a great fast food place");
+        assert_eq!(extract_line(&buffer, 0, 3, 10), "taco ca...");
+    }
+
+    #[test]
+    fn wide_chars() {
+        let mut buffer = Buffer::new(50, 50);
+        let w = plain(&mut buffer).print(0, 0, "a真b");
+        assert_eq!(w, 4);
+        assert_eq!(buffer.get_glyph(1, 0), Some(&('真' as u32 | WIDE_GLYPH)));
+        assert_eq!(
+            buffer.get_glyph(2, 0),
+            Some(&('真' as u32 | WIDE_CONTINUATION))
+        );
+        assert_eq!(buffer.get_glyph(3, 0), Some(&('b' as u32)));
+
+        // half a wide char is not printed
+        plain(&mut buffer)
+            .width(3)
+            .overflow(Overflow::Clip)
+            .print(0, 1, "ab真c");
+        assert_eq!(extract_line(&buffer, 0, 1, 3), "ab ");
+
+        // wide chars break without hyphens
+        let r = plain(&mut buffer).width(6).wrap(0, 2, "こんにちは世界");
+        assert_eq!(r, (6, 3));
+        assert_eq!(buffer.get_glyph(4, 2), Some(&('に' as u32 | WIDE_GLYPH)));
+        assert_eq!(buffer.get_glyph(2, 3), Some(&('は' as u32 | WIDE_GLYPH)));
+        assert_eq!(buffer.get_glyph(0, 4), Some(&('界' as u32 | WIDE_GLYPH)));
+    }
+
+    #[test]
+    fn codepage() {
+        let mut buffer = Buffer::new(50, 50);
+        buffer.set_codepage(Some(Rc::new(Codepage::cp437())));
+        plain(&mut buffer).print(0, 0, "a░");
+        assert_eq!(buffer.get_glyph(0, 0), Some(&('a' as u32)));
+        assert_eq!(buffer.get_glyph(1, 0), Some(&176));
+
+        // to_glyph wins over the codepage
+        plain(&mut buffer)
+            .to_glyph(&|ch| ch as u32)
+            .print(0, 1, "░");
+        assert_eq!(buffer.get_glyph(0, 1), Some(&('░' as u32)));
+    }
+
+    #[test]
+    fn font_slots() {
+        let mut buffer = Buffer::new(10, 5);
+        buffer.font_area(0, 0, 10, 5, 2);
+        plain(&mut buffer).font(1).print(1, 0, "Hi");
+        plain(&mut buffer).print(1, 1, "Hi");
+        assert_eq!(buffer.get_font(0, 0), Some(&2));
+        assert_eq!(buffer.get_font(1, 0), Some(&1));
+        assert_eq!(buffer.get_font(2, 0), Some(&1));
+        // printing without a slot puts the glyphs in the console's font
+        assert_eq!(buffer.get_font(1, 1), Some(&0));
+        assert_eq!(buffer.get_font(0, 1), Some(&2));
+
+        // blit copies the slots with the glyphs
+        let mut dest = Buffer::new(10, 5);
+        buffer.blit(0, 2, &mut dest, 1.0, 1.0, None);
+        assert_eq!(dest.get_font(1, 2), Some(&1));
+        assert_eq!(dest.get_font(0, 2), Some(&2));
+        assert_eq!(dest.get_font(0, 0), Some(&0));
+    }
+
+    #[test]
+    fn wrap_breaks() {
+        let mut buffer = Buffer::new(50, 50);
+        let mut printer = plain(&mut buffer).width(10);
+
+        let r = printer.wrap(0, 0, "a well-known stone\u{AD}masons\u{200B}guild");
+        assert_eq!(extract_line(&buffer, 0, 0, 10), "a well-\0\0\0");
+        assert_eq!(extract_line(&buffer, 0, 1, 10), "known\0\0\0\0\0");
+        assert_eq!(extract_line(&buffer, 0, 2, 10), "stone-\0\0\0\0");
+        assert_eq!(extract_line(&buffer, 0, 3, 10), "masons\0\0\0\0");
+        assert_eq!(extract_line(&buffer, 0, 4, 10), "guild\0\0\0\0\0");
+        assert_eq!(r, (10, 5));
+    }
+
+    #[test]
+    fn wrap_hyphenator() {
+        let mut buffer = Buffer::new(50, 50);
+        let english = Hyphenator::english();
+        let mut printer = plain(&mut buffer).width(10).hyphenator(&english);
+
+        let r = printer.wrap(
+            0,
+            1,
+            "the conflagration exponentially deteriorated the stonemasons' monuments",
+        );
+        assert_eq!(extract_line(&buffer, 0, 1, 10), "the con-\0\0");
+        assert_eq!(extract_line(&buffer, 0, 2, 10), "flagration");
+        assert_eq!(extract_line(&buffer, 0, 3, 10), "exponen-\0\0");
+        assert_eq!(extract_line(&buffer, 0, 4, 10), "tially de-");
+        assert_eq!(extract_line(&buffer, 0, 5, 10), "teriorated");
+        assert_eq!(extract_line(&buffer, 0, 6, 10), "the stone-");
+        assert_eq!(extract_line(&buffer, 0, 7, 10), "masons'\0\0\0");
+        assert_eq!(extract_line(&buffer, 0, 8, 10), "monuments\0");
+        assert_eq!(r, (10, 8));
+    }
 }
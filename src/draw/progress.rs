@@ -1,56 +1,353 @@
-use crate::simple::Buffer;
+use crate::simple::{Buffer, Glyph};
 use crate::RGBA;
 
+/// Creates a [`Progress`] bar drawer for this buffer
 pub fn progress<'a>(buffer: &'a mut Buffer) -> Progress {
     Progress::new(buffer)
 }
 
+/// How finely the fill edge of a progress bar is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarPrecision {
+    /// Whole cells only (only uses bg colors)
+    Cell,
+    /// Half cells using '▌' or '▄' (both are in CP437)
+    Half,
+    /// Eighth cells using the unicode partial block glyphs ('▏'..'▉' or '▁'..'▇')
+    Eighth,
+}
+
+impl BarPrecision {
+    fn steps(&self) -> i32 {
+        match self {
+            BarPrecision::Cell => 1,
+            BarPrecision::Half => 2,
+            BarPrecision::Eighth => 8,
+        }
+    }
+
+    /// The char to use for a cell that is step/steps() full
+    fn partial_char(&self, step: i32, vertical: bool) -> char {
+        let eighths = step * 8 / self.steps();
+        let code = match vertical {
+            // '▁' (1/8) to '▇' (7/8) grow from the bottom
+            true => 0x2580 + eighths as u32,
+            // '▏' (1/8) to '▉' (7/8) grow from the left
+            false => 0x2590 - eighths as u32,
+        };
+        char::from_u32(code).unwrap_or(' ')
+    }
+}
+
+/// Draws progress bars (horizontal or vertical) and pip counters
 pub struct Progress<'a> {
     buffer: &'a mut Buffer,
     fg: Option<RGBA>,
     bg: Option<RGBA>,
     width: i32,
+    height: i32,
+    vertical: bool,
+    precision: BarPrecision,
+    label: Option<String>,
+    label_colors: Option<(RGBA, RGBA)>,
+    pips: Option<(Glyph, Glyph)>,
+    spacing: i32,
+    to_glyph: Option<&'a dyn Fn(char) -> Glyph>,
 }
 
 impl<'a> Progress<'a> {
+    /// Creates a progress bar drawer for this buffer
     pub fn new(buffer: &'a mut Buffer) -> Self {
         Progress {
             buffer,
             fg: None,
             bg: None,
             width: 0,
+            height: 1,
+            vertical: false,
+            precision: BarPrecision::Cell,
+            label: None,
+            label_colors: None,
+            pips: None,
+            spacing: 0,
+            to_glyph: None,
         }
     }
 
+    /// Sets the color of the filled part of the bar (or the filled pips)
     pub fn fg(mut self, fg: RGBA) -> Self {
         self.fg = Some(fg);
         self
     }
 
+    /// Sets the color of the empty part of the bar (or the empty pips)
     pub fn bg(mut self, bg: RGBA) -> Self {
         self.bg = Some(bg);
         self
     }
 
+    /// Sets the width of the bar in cells
     pub fn width(mut self, width: i32) -> Self {
         self.width = width;
         self
     }
 
-    // TODO - Add some alpha blending in transition cell
+    /// Sets the height of the bar in cells, default=1
+    pub fn height(mut self, height: i32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Fill the bar from the bottom up instead of left to right
+    ///
+    /// The length of a vertical bar is its height, the width is its thickness.
+    pub fn vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+
+    /// Sets the precision of the fill edge, default=[`BarPrecision::Cell`]
+    pub fn precision(mut self, precision: BarPrecision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Sets a label that is centered on the bar (horizontal bars only)
+    ///
+    /// Over the filled part, the label is drawn in the bg color and over the empty part in the fg color.
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_owned());
+        self
+    }
+
+    /// Overrides the label colors: (over the filled part, over the empty part)
+    pub fn label_colors(mut self, on_fill: RGBA, on_empty: RGBA) -> Self {
+        self.label_colors = Some((on_fill, on_empty));
+        self
+    }
+
+    /// Draw pips instead of a bar - one cell per unit of max
+    ///
+    /// Filled pips use the full glyph in the fg color, the rest use the empty glyph in the bg color.
+    pub fn pips(mut self, full: Glyph, empty: Glyph) -> Self {
+        self.pips = Some((full, empty));
+        self
+    }
+
+    /// Sets the number of cells between pips, default=0
+    pub fn spacing(mut self, spacing: i32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Sets the char->Glyph conversion function, default=the buffer's codepage (from the console's font) or (ch as u32)
+    ///
+    /// Use [`crate::codepage437::to_glyph`] with CP437 fonts and [`BarPrecision::Half`].
+    pub fn to_glyph(mut self, to_glyph: &'a dyn Fn(char) -> Glyph) -> Self {
+        self.to_glyph = Some(to_glyph);
+        self
+    }
+
+    /// Draws the bar with the top left corner at x,y
     pub fn draw(&mut self, x: i32, y: i32, val: i32, max: i32) {
-        let percent = val as f32 / max as f32;
-        let fill_width = (percent * self.width as f32) as i32;
-        for cx in 0..self.width {
-            if cx <= fill_width {
-                if let Some(ref fg) = self.fg {
-                    self.buffer.back(x + cx, y, *fg);
-                }
-            } else {
-                if let Some(ref bg) = self.bg {
-                    self.buffer.back(x + cx, y, *bg);
+        if let Some((full, empty)) = self.pips {
+            self.draw_pips(x, y, val, max, full, empty);
+            return;
+        }
+
+        let (len, thickness) = match self.vertical {
+            true => (self.height, self.width),
+            false => (self.width, self.height),
+        };
+        let steps = self.precision.steps();
+        let percent = match max > 0 {
+            true => (val as f32 / max as f32).clamp(0.0, 1.0),
+            false => 0.0,
+        };
+        let filled = (percent * (len * steps) as f32).round() as i32;
+        let full_cells = filled / steps;
+        let part = filled % steps;
+
+        for i in 0..len {
+            for j in 0..thickness {
+                let (cx, cy) = match self.vertical {
+                    true => (x + j, y + len - 1 - i),
+                    false => (x + i, y + j),
+                };
+                if i < full_cells {
+                    self.fill_cell(cx, cy, self.fg);
+                } else if i == full_cells && part > 0 {
+                    let glyph = self.buffer.glyph_with(
+                        self.to_glyph,
+                        self.precision.partial_char(part, self.vertical),
+                    );
+                    match self.fg {
+                        None => self.fill_cell(cx, cy, self.bg),
+                        Some(_) => self.buffer.draw_opt(cx, cy, Some(glyph), self.fg, self.bg),
+                    }
+                } else {
+                    self.fill_cell(cx, cy, self.bg);
                 }
             }
         }
+
+        if !self.vertical {
+            self.draw_label(x, y, full_cells, part * 2 >= steps);
+        }
+    }
+
+    /// Sets the back of the cell, clearing any partial glyph if we draw those
+    fn fill_cell(&mut self, x: i32, y: i32, color: Option<RGBA>) {
+        if let Some(color) = color {
+            self.buffer.back(x, y, color);
+            if self.precision != BarPrecision::Cell {
+                self.buffer.glyph(x, y, 0);
+            }
+        }
+    }
+
+    fn draw_label(&mut self, x: i32, y: i32, full_cells: i32, part_filled: bool) {
+        let label = match self.label {
+            None => return,
+            Some(ref label) => label,
+        };
+        let (on_fill, on_empty) = self.label_colors.unwrap_or((
+            self.bg.unwrap_or(RGBA::rgb(0, 0, 0)),
+            self.fg.unwrap_or(RGBA::rgb(255, 255, 255)),
+        ));
+
+        let label_len = label.chars().count() as i32;
+        let start = (self.width - label_len).max(0) / 2;
+        let ly = y + (self.height - 1) / 2;
+
+        for (i, ch) in label.chars().take(self.width.max(0) as usize).enumerate() {
+            let ci = start + i as i32;
+            let filled = ci < full_cells || (ci == full_cells && part_filled);
+            let (fg, bg) = match filled {
+                true => (on_fill, self.fg),
+                false => (on_empty, self.bg),
+            };
+            let glyph = self.buffer.glyph_with(self.to_glyph, ch);
+            self.buffer.draw_opt(x + ci, ly, Some(glyph), Some(fg), bg);
+        }
+    }
+
+    fn draw_pips(&mut self, x: i32, y: i32, val: i32, max: i32, full: Glyph, empty: Glyph) {
+        let step = 1 + self.spacing.max(0);
+        for i in 0..max {
+            let (glyph, fg) = match i < val {
+                true => (full, self.fg),
+                false => (empty, self.bg),
+            };
+            match self.vertical {
+                true => self
+                    .buffer
+                    .draw_opt(x, y + (max - 1 - i) * step, Some(glyph), fg, None),
+                false => self.buffer.draw_opt(x + i * step, y, Some(glyph), fg, None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RED: RGBA = RGBA::rgb(255, 0, 0);
+    const BLUE: RGBA = RGBA::rgb(0, 0, 255);
+
+    fn extract_line(buf: &Buffer, x: i32, y: i32, width: i32) -> String {
+        let mut output = "".to_string();
+        for cx in x..x + width {
+            if let Some(g) = buf.get_glyph(cx, y) {
+                output.push(char::from_u32(*g).unwrap());
+            }
+        }
+        output
+    }
+
+    #[test]
+    fn whole_cells() {
+        let mut buffer = Buffer::new(20, 5);
+        progress(&mut buffer)
+            .width(10)
+            .fg(RED)
+            .bg(BLUE)
+            .draw(0, 0, 5, 10);
+
+        for x in 0..5 {
+            assert_eq!(buffer.get_back(x, 0), Some(&RED));
+        }
+        for x in 5..10 {
+            assert_eq!(buffer.get_back(x, 0), Some(&BLUE));
+        }
+    }
+
+    #[test]
+    fn eighth_cells() {
+        let mut buffer = Buffer::new(20, 5);
+        progress(&mut buffer)
+            .width(4)
+            .fg(RED)
+            .bg(BLUE)
+            .precision(BarPrecision::Eighth)
+            .draw(0, 0, 11, 32);
+
+        // 11/32 of 4 cells = 1 cell + 3/8
+        assert_eq!(extract_line(&buffer, 0, 0, 4), "\0▍\0\0");
+        assert_eq!(buffer.get_back(0, 0), Some(&RED));
+        assert_eq!(buffer.get_fore(1, 0), Some(&RED));
+        assert_eq!(buffer.get_back(1, 0), Some(&BLUE));
+        assert_eq!(buffer.get_back(2, 0), Some(&BLUE));
+    }
+
+    #[test]
+    fn vertical_half_cells() {
+        let mut buffer = Buffer::new(5, 5);
+        progress(&mut buffer)
+            .width(1)
+            .height(4)
+            .vertical(true)
+            .fg(RED)
+            .bg(BLUE)
+            .precision(BarPrecision::Half)
+            .draw(0, 0, 3, 8);
+
+        assert_eq!(buffer.get_back(0, 3), Some(&RED));
+        assert_eq!(buffer.get_glyph(0, 2), Some(&('▄' as u32)));
+        assert_eq!(buffer.get_back(0, 1), Some(&BLUE));
+        assert_eq!(buffer.get_back(0, 0), Some(&BLUE));
+    }
+
+    #[test]
+    fn label_contrast() {
+        let mut buffer = Buffer::new(20, 5);
+        progress(&mut buffer)
+            .width(10)
+            .fg(RED)
+            .bg(BLUE)
+            .label("HP 5/10")
+            .draw(0, 0, 5, 10);
+
+        assert_eq!(extract_line(&buffer, 0, 0, 10), "\0HP 5/10\0\0");
+        assert_eq!(buffer.get_fore(1, 0), Some(&BLUE));
+        assert_eq!(buffer.get_back(1, 0), Some(&RED));
+        assert_eq!(buffer.get_fore(6, 0), Some(&RED));
+        assert_eq!(buffer.get_back(6, 0), Some(&BLUE));
+    }
+
+    #[test]
+    fn pips() {
+        let mut buffer = Buffer::new(20, 5);
+        progress(&mut buffer)
+            .fg(RED)
+            .bg(BLUE)
+            .pips('*' as u32, '.' as u32)
+            .spacing(1)
+            .draw(0, 0, 2, 4);
+
+        assert_eq!(extract_line(&buffer, 0, 0, 7), "*\0*\0.\0.");
+        assert_eq!(buffer.get_fore(2, 0), Some(&RED));
+        assert_eq!(buffer.get_fore(4, 0), Some(&BLUE));
     }
 }
@@ -1,26 +1,330 @@
 // use crate::Buffer;
 use uni_gl::{WebGLRenderingContext, WebGLTexture};
 
-use crate::{console, simple::set_texture_params};
+use crate::{
+    console,
+    simple::{set_texture_params, Glyph},
+    to_rgba, BitmapFont, Codepage, GlyphRegistry, Image, RGBA,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The layout of a font image - cell size, glyph count, chars per row, codepage, etc...
+///
+/// Load one from a sidecar descriptor with [`FontInfo::parse`] (see [`crate::AppContext::load_font_with_info`]),
+/// or from a file name that ends with the cell size (e.g. 'name_8x8.png') with [`FontInfo::from_path`].
+///
+/// ```text
+/// # terminal.font
+/// size = 8x8
+/// count = 256
+/// columns = 16
+/// codepage = cp437
+/// transparency = #000000
+/// full_color = false
+/// # water - glyphs 224-227, 250ms each
+/// animation = 224 4 250
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FontInfo {
+    /// The size of a cell in pixels
+    pub char_size: Option<(u32, u32)>,
+    /// The number of glyphs, default=all of the cells in the image
+    pub count: Option<u32>,
+    /// The number of glyphs in each row of the image, default=as many as fit
+    pub columns: Option<u32>,
+    /// The name of a built in codepage (see [`Codepage::builtin`])
+    pub codepage: Option<String>,
+    /// How the transparent pixels of the image are found, default=[`FontTransparency::Auto`]
+    pub transparency: FontTransparency,
+    /// true = the glyphs keep their own colors, false = the glyphs are tinted with the fg color (default)
+    pub full_color: bool,
+    /// The animated glyph ranges (see [`GlyphAnimation`])
+    pub animations: Vec<GlyphAnimation>,
+}
+
+impl FontInfo {
+    /// Constructs the info for a font with the given cell size
+    pub fn new(char_width: u32, char_height: u32) -> Self {
+        FontInfo {
+            char_size: Some((char_width, char_height)),
+            ..FontInfo::default()
+        }
+    }
+
+    /// Constructs the info from a file name that ends with the cell size (e.g. 'name_8x8.png')
+    pub fn from_path(filepath: &str) -> Result<Self, FontErr> {
+        let (width, height) = parse_char_size(filepath)?;
+        Ok(FontInfo::new(width, height))
+    }
+
+    /// Parses a sidecar descriptor
+    ///
+    /// Each line is `key = value` - the keys are `size` (e.g. 8x8), `count`, `columns`, `codepage`,
+    /// `transparency` (`auto`, `alpha`, `greyscale`, `none` or a key color, e.g. #ff00ff),
+    /// `full_color` (true or false) and `animation` (the first glyph, the number of frames, the ms per frame
+    /// and optionally `lockstep`, e.g. `224 4 250`), which can be given more than once.
+    /// Blank lines and lines starting with '#' are skipped.
+    pub fn parse(text: &str) -> Result<Self, FontErr> {
+        let mut info = FontInfo::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let bad_line = || FontErr::BadLine(i, line.to_owned());
+            let (key, value) = line.split_once('=').ok_or_else(bad_line)?;
+            let value = value.trim();
+            let number = || value.parse::<u32>().map_err(|_| bad_line());
+            match key.trim() {
+                "size" | "cell" => info.char_size = Some(parse_size(value).ok_or_else(bad_line)?),
+                "count" => info.count = Some(number()?),
+                "columns" => info.columns = Some(number()?),
+                "codepage" => {
+                    if Codepage::builtin(value).is_none() {
+                        return Err(FontErr::UnknownCodepage(value.to_owned()));
+                    }
+                    info.codepage = Some(value.to_owned());
+                }
+                "transparency" | "transparent" => {
+                    info.transparency = FontTransparency::from_name(value).ok_or_else(bad_line)?
+                }
+                "full_color" => info.full_color = value.parse().map_err(|_| bad_line())?,
+                "animation" => info
+                    .animations
+                    .push(GlyphAnimation::parse(value).ok_or_else(bad_line)?),
+                _ => return Err(bad_line()),
+            }
+        }
+        Ok(info)
+    }
+}
+
+/// The most animated glyph ranges a font can have
+pub const MAX_ANIMATIONS: usize = 8;
+
+/// A range of glyphs that is played as an animation
+///
+/// Cells set to the first glyph are animated by the renderer, each with its own phase (unless `lockstep`),
+/// so the buffer does not need to change.  The other glyphs in the range are the later frames.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GlyphAnimation {
+    /// The glyph of the first frame
+    pub first: Glyph,
+    /// The number of frames (glyphs)
+    pub frames: u32,
+    /// The time each frame is shown, in ms
+    pub frame_ms: u32,
+    /// true = all of the cells show the same frame, false = each cell starts at a different point in the cycle
+    pub lockstep: bool,
+}
+
+impl GlyphAnimation {
+    /// Constructs an animation of the frames that start at the first glyph
+    pub fn new(first: Glyph, frames: u32, frame_ms: u32) -> Self {
+        GlyphAnimation {
+            first,
+            frames,
+            frame_ms,
+            lockstep: false,
+        }
+    }
+
+    /// Sets whether all of the cells show the same frame, default=false
+    pub fn lockstep(mut self, lockstep: bool) -> Self {
+        self.lockstep = lockstep;
+        self
+    }
+
+    // parses "first frames ms [lockstep]"
+    fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.split_whitespace();
+        let mut number = || parts.next()?.parse::<u32>().ok();
+        let anim = GlyphAnimation::new(number()?, number()?, number()?);
+        match parts.next() {
+            None => Some(anim),
+            Some("lockstep") => Some(anim.lockstep(true)),
+            Some(_) => None,
+        }
+    }
+
+    /// How far through the cycle the animation is at the time (in seconds), from 0 to 1
+    pub fn cycle(&self, time: f64) -> f32 {
+        let cycle_ms = (self.frames.max(1) * self.frame_ms.max(1)) as f64;
+        ((time * 1000.0) % cycle_ms / cycle_ms) as f32
+    }
+
+    /// The glyph shown at the time (in seconds) by a cell with the phase (from 0 to 1)
+    pub fn glyph_at(&self, time: f64, phase: f32) -> Glyph {
+        let phase = if self.lockstep { 0.0 } else { phase };
+        let frame = ((self.cycle(time) + phase).fract() * self.frames as f32) as u32;
+        self.first + frame.min(self.frames.max(1) - 1)
+    }
+}
+
+/// How the transparent pixels of a font image are found
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum FontTransparency {
+    /// If the top left pixel is opaque, its color is the key - and a black key means greyscale as alpha
+    #[default]
+    Auto,
+    /// The pixels with this color are transparent
+    ColorKey(RGBA),
+    /// The alpha channel of the image is used as is
+    Alpha,
+    /// The brightness of each pixel is its alpha (and the glyphs are white)
+    Greyscale,
+    /// All of the pixels are opaque
+    None,
+}
+
+impl FontTransparency {
+    /// Finds the transparency for a descriptor value - `auto`, `alpha`, `greyscale`, `none` or a key color
+    pub fn from_name(name: &str) -> Option<Self> {
+        let transparency = match name.to_lowercase().as_str() {
+            "auto" => FontTransparency::Auto,
+            "alpha" => FontTransparency::Alpha,
+            "greyscale" | "grayscale" => FontTransparency::Greyscale,
+            "none" => FontTransparency::None,
+            _ => FontTransparency::ColorKey(to_rgba(name)?),
+        };
+        Some(transparency)
+    }
+}
+
+/// An error loading a font or its descriptor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FontErr {
+    /// The file name does not end with the cell size (e.g. 'name_8x8.png')
+    NoCharSize(String),
+    /// The descriptor line (index, text) is not `key = value`, or has an unknown key or a bad value
+    BadLine(usize, String),
+    /// The codepage is not one of the built in codepages
+    UnknownCodepage(String),
+    /// The image could not be decoded
+    BadImage(String),
+    /// The BDF or PSF font could not be parsed
+    BadFontFile(String),
+    /// The cell size, columns or count do not fit the image
+    BadLayout(String),
+    /// The animation does not fit the glyphs, or there are too many (see [`MAX_ANIMATIONS`])
+    BadAnimation(String),
+}
+
+impl std::fmt::Display for FontErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FontErr::NoCharSize(path) => write!(
+                f,
+                "font file name must end with cell size information ('_8x8.' in 'name_8x8.png') - {}",
+                path
+            ),
+            FontErr::BadLine(line, text) => write!(f, "bad font info at line {} - {}", line, text),
+            FontErr::UnknownCodepage(name) => write!(f, "unknown codepage - {}", name),
+            FontErr::BadImage(msg) => write!(f, "failed to decode font image - {}", msg),
+            FontErr::BadFontFile(msg) => write!(f, "failed to parse font file - {}", msg),
+            FontErr::BadLayout(msg) => write!(f, "font layout does not fit the image - {}", msg),
+            FontErr::BadAnimation(msg) => write!(f, "bad glyph animation - {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for FontErr {}
 
 pub struct Font {
     img_size: (u32, u32),
     char_size: (u32, u32),
+    columns: u32,
     count: u32,
+    full_color: bool,
+    atlas: Image,
+    codepage: RefCell<Option<Rc<Codepage>>>,
+    glyphs: RefCell<Option<Rc<GlyphRegistry>>>,
+    animations: RefCell<Vec<GlyphAnimation>>,
     pub(crate) texture: WebGLTexture,
 }
 
 impl Font {
+    /// Constructs a font from the bytes of an image with the given cell size
+    ///
+    /// Panics if the image can not be used, see [`Font::from_bytes`].
     pub fn new(gl: &WebGLRenderingContext, bytes: &[u8], char_size: (u32, u32)) -> Self {
-        let mut font = Font {
-            img_size: (0, 0),
-            char_size,
-            count: 0,
-            texture: create_font_texture(gl),
+        let info = FontInfo::new(char_size.0, char_size.1);
+        match Font::from_bytes(gl, bytes, &info) {
+            Err(e) => panic!("Failed to load font.  {}", e),
+            Ok(font) => font,
+        }
+    }
+
+    /// Constructs a font from the bytes of an image and its layout
+    pub fn from_bytes(
+        gl: &WebGLRenderingContext,
+        bytes: &[u8],
+        info: &FontInfo,
+    ) -> Result<Self, FontErr> {
+        console(format!("load font image - {}", bytes.len()));
+        let img = image::load_from_memory(bytes)
+            .map_err(|e| FontErr::BadImage(e.to_string()))?
+            .to_rgba8();
+        Font::from_image(gl, Image { img }, info)
+    }
+
+    /// Constructs a font from a BDF or PSF font (see [`BitmapFont`])
+    ///
+    /// The font gets the codepoint mapping of the bitmap font as its codepage.
+    pub fn from_bitmap(gl: &WebGLRenderingContext, bitmap: &BitmapFont) -> Result<Self, FontErr> {
+        let font = Font::from_image(gl, bitmap.to_image(), &bitmap.info())?;
+        if let Some(codepage) = bitmap.codepage() {
+            font.set_codepage(Some(Rc::new(codepage.clone())));
+        }
+        Ok(font)
+    }
+
+    /// Constructs a font from an atlas image and its layout
+    pub fn from_image(
+        gl: &WebGLRenderingContext,
+        image: Image,
+        info: &FontInfo,
+    ) -> Result<Self, FontErr> {
+        let mut img = image.img;
+        let (char_width, char_height, columns, count) = layout(info, img.width(), img.height())?;
+        let codepage = match info.codepage {
+            None => None,
+            Some(ref name) => match Codepage::builtin(name) {
+                None => return Err(FontErr::UnknownCodepage(name.clone())),
+                Some(codepage) => Some(Rc::new(codepage)),
+            },
         };
 
-        font.load_font_img(bytes, gl);
-        font
+        process_image(&mut img, info.transparency);
+
+        let texture = create_font_texture(gl);
+        gl.tex_image2d(
+            uni_gl::TextureBindPoint::Texture2d, // target
+            0,                                   // level
+            img.width() as u16,                  // width
+            img.height() as u16,                 // height
+            uni_gl::PixelFormat::Rgba,           // format
+            uni_gl::PixelType::UnsignedByte,     // type
+            &*img,                               // data
+        );
+
+        let font = Font {
+            img_size: (img.width(), img.height()),
+            char_size: (char_width, char_height),
+            columns,
+            count,
+            full_color: info.full_color,
+            atlas: Image { img },
+            codepage: RefCell::new(codepage),
+            glyphs: RefCell::new(None),
+            animations: RefCell::new(Vec::new()),
+            texture,
+        };
+        for anim in info.animations.iter() {
+            font.animate(*anim)?;
+        }
+        Ok(font)
     }
 
     pub fn img_width(&self) -> u32 {
@@ -39,96 +343,196 @@ impl Font {
         self.char_size
     }
 
+    /// The number of glyphs in each row of the image
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
     pub fn count(&self) -> u32 {
         self.count
     }
 
-    fn load_font_img(&mut self, buf: &[u8], gl: &WebGLRenderingContext) {
-        console(format!("load font image - {}", buf.len()));
-        let mut img = image::load_from_memory(&buf).unwrap().to_rgba8();
-        process_image(&mut img);
+    /// true = the glyphs keep their own colors, false = the glyphs are tinted with the fg color
+    pub fn full_color(&self) -> bool {
+        self.full_color
+    }
 
-        self.img_size = (img.width() as u32, img.height() as u32);
-        self.count =
-            (self.img_width() / self.char_width()) * (self.img_height() / self.char_height());
+    /// The processed font image (after transparency is applied) that is loaded into the texture
+    pub fn atlas(&self) -> &Image {
+        &self.atlas
+    }
 
-        gl.bind_texture(&self.texture);
+    /// The mapping of chars to the glyphs of this font (if any)
+    pub fn codepage(&self) -> Option<Rc<Codepage>> {
+        self.codepage.borrow().clone()
+    }
 
-        gl.tex_image2d(
-            uni_gl::TextureBindPoint::Texture2d, // target
-            0,                                   // level
-            img.width() as u16,                  // width
-            img.height() as u16,                 // height
-            uni_gl::PixelFormat::Rgba,           // format
-            uni_gl::PixelType::UnsignedByte,     // type
-            &*img,                               // data
-        );
+    /// Attaches the mapping of chars to the glyphs of this font
+    ///
+    /// Consoles that use the font give it to their buffer, so printers use it by default.
+    pub fn set_codepage(&self, codepage: Option<Rc<Codepage>>) {
+        *self.codepage.borrow_mut() = codepage;
     }
-}
 
-pub fn parse_char_size(filepath: &str) -> (u32, u32) {
-    let mut char_width = 0;
-    let mut char_height = 0;
+    /// The names of the glyphs of this font (if any)
+    pub fn glyph_registry(&self) -> Option<Rc<GlyphRegistry>> {
+        self.glyphs.borrow().clone()
+    }
+
+    /// Attaches the names of the glyphs of this font
+    ///
+    /// Consoles that use the font give it to their buffer, so printers and the `#{name}` markup use it.
+    pub fn set_glyph_registry(&self, glyphs: Option<Rc<GlyphRegistry>>) {
+        *self.glyphs.borrow_mut() = glyphs;
+    }
+
+    /// A copy of the animated glyph ranges of this font, see [`Font::with_animations`] to read them without copying
+    pub fn animations(&self) -> Vec<GlyphAnimation> {
+        self.animations.borrow().clone()
+    }
 
-    let start = match filepath.rfind('_') {
-        None => {
-            panic!("Failed to load font.  Font file name must end with cell size information ('_8x8.' in 'name_8x8.png') - {}", filepath);
+    /// Calls the function with the animated glyph ranges of this font
+    ///
+    /// The font cannot be animated (see [`Font::animate`]) from inside the function.
+    pub fn with_animations<R>(&self, func: impl FnOnce(&[GlyphAnimation]) -> R) -> R {
+        func(&self.animations.borrow())
+    }
+
+    /// Animates the glyph range, replacing any animation with the same first glyph
+    ///
+    /// Fails if the frames are not glyphs of this font, or if the font already has [`MAX_ANIMATIONS`].
+    pub fn animate(&self, anim: GlyphAnimation) -> Result<(), FontErr> {
+        if anim.frames == 0
+            || anim.frame_ms == 0
+            || anim.first.saturating_add(anim.frames) > self.count
+        {
+            return Err(FontErr::BadAnimation(format!(
+                "{} frames of {}ms from glyph {} in a font with {} glyphs",
+                anim.frames, anim.frame_ms, anim.first, self.count
+            )));
         }
-        Some(idx) => idx,
-    };
-    let end = match filepath.rfind('.') {
-        None => {
-            panic!("Failed to load font.  Font file name must end with cell size information ('_8x8.' in 'name_8x8.png') - {}", filepath);
+        let mut animations = self.animations.borrow_mut();
+        if let Some(old) = animations.iter_mut().find(|a| a.first == anim.first) {
+            *old = anim;
+        } else if animations.len() < MAX_ANIMATIONS {
+            animations.push(anim);
+        } else {
+            return Err(FontErr::BadAnimation(format!(
+                "more than {} animations",
+                MAX_ANIMATIONS
+            )));
         }
-        Some(idx) => idx,
-    };
-    if start > 0 && end > 0 {
-        let subpath = &filepath[start + 1..end];
-        let charsize: Vec<&str> = subpath.split('x').collect();
-        char_width = match charsize[0].parse::<u32>() {
-            Err(_) => {
-                panic!("Failed to load font.  Font file name must end with cell size information ('_8x8.' in 'name_8x8.png') - {}", filepath);
-            }
-            Ok(val) => val,
-        };
-        char_height = match charsize[1].parse::<u32>() {
-            Err(_) => {
-                panic!("Failed to load font.  Font file name must end with cell size information ('_8x8.' in 'name_8x8.png') - {}", filepath);
-            }
-            Ok(val) => val,
-        };
+        Ok(())
     }
-    (char_width, char_height)
-}
-
-fn process_image(img: &mut image::RgbaImage) {
-    let pixel = img.get_pixel(0, 0);
-    let alpha = pixel[3];
-    if alpha == 255 {
-        let transparent_color = (pixel[0], pixel[1], pixel[2]);
-        let greyscale = transparent_color == (0, 0, 0);
-        crate::console(&format!(
-            "{}transparent color: {:?}",
-            if greyscale { "greyscale " } else { "" },
-            transparent_color
-        ));
-        let (width, height) = img.dimensions();
-        for y in 0..height {
-            for x in 0..width {
-                let pixel = img.get_pixel_mut(x, y);
-                if (pixel[0], pixel[1], pixel[2]) == transparent_color {
-                    pixel[3] = 0;
-                    pixel[0] = 0;
-                    pixel[1] = 0;
-                    pixel[2] = 0;
-                } else if greyscale && pixel[0] == pixel[1] && pixel[1] == pixel[2] {
-                    let alpha = pixel[0];
-                    pixel[0] = 255;
-                    pixel[1] = 255;
-                    pixel[2] = 255;
-                    pixel[3] = alpha;
-                }
+
+    /// Stops animating all of the glyphs
+    pub fn clear_animations(&self) {
+        self.animations.borrow_mut().clear();
+    }
+}
+
+/// The most glyphs a font can have (the shader reads 16 bits of the glyph)
+pub(crate) const MAX_GLYPHS: u32 = 0x1_0000;
+
+// checks the info against the image size - returns the char width, char height, columns and count
+pub(crate) fn layout(
+    info: &FontInfo,
+    img_width: u32,
+    img_height: u32,
+) -> Result<(u32, u32, u32, u32), FontErr> {
+    let (char_width, char_height) = info
+        .char_size
+        .ok_or_else(|| FontErr::BadLayout("missing cell size".to_owned()))?;
+    if char_width == 0 || char_height == 0 || char_width > img_width || char_height > img_height {
+        return Err(FontErr::BadLayout(format!(
+            "cell size {}x{} in a {}x{} image",
+            char_width, char_height, img_width, img_height
+        )));
+    }
+    let max_columns = img_width / char_width;
+    let columns = info.columns.unwrap_or(max_columns);
+    if columns == 0 || columns > max_columns {
+        return Err(FontErr::BadLayout(format!(
+            "{} columns of {} pixels in a {} pixel wide image",
+            columns, char_width, img_width
+        )));
+    }
+    let max_count = columns * (img_height / char_height);
+    let count = info.count.unwrap_or(max_count);
+    if count > max_count {
+        return Err(FontErr::BadLayout(format!(
+            "{} glyphs in an image with room for {}",
+            count, max_count
+        )));
+    }
+    if count > MAX_GLYPHS {
+        return Err(FontErr::BadLayout(format!(
+            "more than {} glyphs",
+            MAX_GLYPHS
+        )));
+    }
+    Ok((char_width, char_height, columns, count))
+}
+
+// the columns for an atlas of glyphs that is about square (at least 16)
+pub(crate) fn atlas_columns(count: u32, char_size: (u32, u32)) -> u32 {
+    let (width, height) = char_size;
+    let columns = ((count * height) as f64 / width as f64).sqrt().ceil() as u32;
+    columns.clamp(16, count.max(16))
+}
+
+/// Finds the cell size at the end of a font file name (e.g. '_8x8.' in 'name_8x8.png')
+pub fn parse_char_size(filepath: &str) -> Result<(u32, u32), FontErr> {
+    let err = || FontErr::NoCharSize(filepath.to_owned());
+    let start = filepath.rfind('_').ok_or_else(err)?;
+    let end = filepath.rfind('.').ok_or_else(err)?;
+    if end <= start {
+        return Err(err());
+    }
+    parse_size(&filepath[start + 1..end]).ok_or_else(err)
+}
+
+// parses "8x8"
+fn parse_size(text: &str) -> Option<(u32, u32)> {
+    let (width, height) = text.split_once('x')?;
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
+
+pub(crate) fn process_image(img: &mut image::RgbaImage, transparency: FontTransparency) {
+    let (key, greyscale) = match transparency {
+        FontTransparency::Alpha => return,
+        FontTransparency::None => {
+            img.pixels_mut().for_each(|pixel| pixel[3] = 255);
+            return;
+        }
+        FontTransparency::Greyscale => {
+            for pixel in img.pixels_mut() {
+                let luma =
+                    (pixel[0] as u32 * 299 + pixel[1] as u32 * 587 + pixel[2] as u32 * 114) / 1000;
+                *pixel = image::Rgba([255, 255, 255, luma as u8]);
+            }
+            return;
+        }
+        FontTransparency::ColorKey(color) => ((color.0, color.1, color.2), false),
+        FontTransparency::Auto => {
+            let pixel = img.get_pixel(0, 0);
+            if pixel[3] != 255 {
+                return;
             }
+            let key = (pixel[0], pixel[1], pixel[2]);
+            (key, key == (0, 0, 0))
+        }
+    };
+    crate::console(&format!(
+        "{}transparent color: {:?}",
+        if greyscale { "greyscale " } else { "" },
+        key
+    ));
+    for pixel in img.pixels_mut() {
+        if (pixel[0], pixel[1], pixel[2]) == key {
+            *pixel = image::Rgba([0, 0, 0, 0]);
+        } else if greyscale && pixel[0] == pixel[1] && pixel[1] == pixel[2] {
+            *pixel = image::Rgba([255, 255, 255, pixel[0]]);
         }
     }
 }
@@ -139,3 +543,109 @@ fn create_font_texture(gl: &WebGLRenderingContext) -> WebGLTexture {
     set_texture_params(gl, true);
     tex
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn char_size() {
+        assert_eq!(parse_char_size("resources/terminal_8x8.png"), Ok((8, 8)));
+        assert_eq!(parse_char_size("Buddy--graphical_10x12.png"), Ok((10, 12)));
+        assert_eq!(
+            parse_char_size("resources/subcell.png"),
+            Err(FontErr::NoCharSize("resources/subcell.png".to_owned()))
+        );
+        assert!(parse_char_size("fonts_8x8/terminal").is_err());
+        assert!(parse_char_size("terminal_8.png").is_err());
+    }
+
+    #[test]
+    fn transparency() {
+        let pixels = [
+            0, 0, 0, 255, 128, 128, 128, 255, 255, 0, 255, 255, 10, 20, 30, 40,
+        ];
+        let process = |transparency| {
+            let mut img = image::RgbaImage::from_raw(4, 1, pixels.to_vec()).unwrap();
+            process_image(&mut img, transparency);
+            img.into_raw()
+        };
+        assert_eq!(
+            process(FontTransparency::Auto),
+            [0, 0, 0, 0, 255, 255, 255, 128, 255, 0, 255, 255, 10, 20, 30, 40]
+        );
+        assert_eq!(
+            process(FontTransparency::ColorKey(RGBA::rgb(255, 0, 255))),
+            [0, 0, 0, 255, 128, 128, 128, 255, 0, 0, 0, 0, 10, 20, 30, 40]
+        );
+        assert_eq!(process(FontTransparency::Alpha), pixels);
+        assert_eq!(
+            process(FontTransparency::Greyscale),
+            [255, 255, 255, 0, 255, 255, 255, 128, 255, 255, 255, 105, 255, 255, 255, 18]
+        );
+        assert_eq!(
+            process(FontTransparency::None),
+            [0, 0, 0, 255, 128, 128, 128, 255, 255, 0, 255, 255, 10, 20, 30, 255]
+        );
+    }
+
+    #[test]
+    fn info() {
+        let text = "# terminal\nsize = 8x10\ncount = 128\n\ncolumns=16\ncodepage = CP437\ntransparency = #f0f\nfull_color = true";
+        let info = FontInfo::parse(text).unwrap();
+        assert_eq!(info.char_size, Some((8, 10)));
+        assert_eq!(info.count, Some(128));
+        assert_eq!(info.columns, Some(16));
+        assert_eq!(info.codepage, Some("CP437".to_owned()));
+        assert_eq!(
+            info.transparency,
+            FontTransparency::ColorKey(RGBA::rgb(255, 0, 255))
+        );
+        assert!(info.full_color);
+        assert_eq!(
+            FontInfo::parse("transparency = Greyscale")
+                .unwrap()
+                .transparency,
+            FontTransparency::Greyscale
+        );
+
+        assert_eq!(FontInfo::parse("").unwrap(), FontInfo::default());
+        assert_eq!(
+            FontInfo::parse("size = 8x8\ncount = lots"),
+            Err(FontErr::BadLine(1, "count = lots".to_owned()))
+        );
+        assert_eq!(
+            FontInfo::parse("colour = red"),
+            Err(FontErr::BadLine(0, "colour = red".to_owned()))
+        );
+        assert_eq!(
+            FontInfo::parse("codepage = taco"),
+            Err(FontErr::UnknownCodepage("taco".to_owned()))
+        );
+    }
+
+    #[test]
+    fn animation() {
+        let text = "animation = 224 4 250
+animation = 16 2 500 lockstep";
+        let info = FontInfo::parse(text).unwrap();
+        assert_eq!(
+            info.animations,
+            vec![
+                GlyphAnimation::new(224, 4, 250),
+                GlyphAnimation::new(16, 2, 500).lockstep(true)
+            ]
+        );
+        assert!(FontInfo::parse("animation = 224 4").is_err());
+        assert!(FontInfo::parse("animation = 224 4 250 taco").is_err());
+
+        let water = GlyphAnimation::new(224, 4, 250);
+        assert_eq!(water.cycle(0.5), 0.5);
+        assert_eq!(water.cycle(1.25), 0.25);
+        assert_eq!(water.glyph_at(0.0, 0.0), 224);
+        assert_eq!(water.glyph_at(0.3, 0.0), 225);
+        assert_eq!(water.glyph_at(0.3, 0.5), 227);
+        assert_eq!(water.glyph_at(0.8, 0.5), 225);
+        assert_eq!(water.lockstep(true).glyph_at(0.3, 0.5), 225);
+    }
+}
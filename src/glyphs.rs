@@ -0,0 +1,206 @@
+use crate::simple::{parse_glyph_code, Glyph};
+use crate::{to_rgba, RGBA};
+use std::collections::HashMap;
+
+/// A named glyph - the glyph and its default colors
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GlyphDef {
+    /// The glyph (atlas index)
+    pub glyph: Glyph,
+    /// The default fg (if any)
+    pub fg: Option<RGBA>,
+    /// The default bg (if any)
+    pub bg: Option<RGBA>,
+}
+
+impl GlyphDef {
+    /// Constructs a named glyph without default colors
+    pub fn new(glyph: Glyph) -> Self {
+        GlyphDef {
+            glyph,
+            fg: None,
+            bg: None,
+        }
+    }
+
+    /// Sets the default fg
+    pub fn fg(mut self, fg: RGBA) -> Self {
+        self.fg = Some(fg);
+        self
+    }
+
+    /// Sets the default bg
+    pub fn bg(mut self, bg: RGBA) -> Self {
+        self.bg = Some(bg);
+        self
+    }
+}
+
+/// Names for the glyphs of a font (e.g. "wall.stone" or "player") and their default colors
+///
+/// Attach one to a [`crate::Font`] (see [`crate::AppContext::set_font_glyph_registry`]) and the consoles that
+/// use the font pass it to their buffer, where the printers and the `#{name}` markup pick it up.
+/// Swapping tilesets only needs a new manifest (see [`GlyphRegistry::parse`]).
+#[derive(Debug, Clone, Default)]
+pub struct GlyphRegistry {
+    glyphs: HashMap<String, GlyphDef>,
+}
+
+impl GlyphRegistry {
+    /// Constructs an empty registry
+    pub fn new() -> Self {
+        GlyphRegistry::default()
+    }
+
+    /// Parses a registry from a manifest
+    ///
+    /// Each line is a name, an '=', the glyph (decimal or 0x hex), and optionally the default
+    /// fg and bg colors (see [`to_rgba`], without spaces), where '-' is no color.  Blank lines and lines starting
+    /// with '#' are skipped.
+    ///
+    /// ```text
+    /// # dungeon
+    /// wall.stone = 0xDB #888 #222
+    /// floor = 250 #444
+    /// door = 43 - #630
+    /// player = 64 #ff0
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, GlyphRegistryErr> {
+        let mut registry = GlyphRegistry::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |kind| GlyphRegistryErr { kind, line: i };
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| err(GlyphRegistryErrKind::MissingEquals))?;
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(err(GlyphRegistryErrKind::MissingName));
+            }
+
+            let mut parts = value.split_whitespace();
+            let glyph = parse_glyph_code(parts.next().unwrap_or(""))
+                .ok_or_else(|| err(GlyphRegistryErrKind::BadGlyph))?;
+            let mut color = || match parts.next() {
+                None | Some("-") => Ok(None),
+                Some(txt) => to_rgba(txt)
+                    .map(Some)
+                    .ok_or_else(|| err(GlyphRegistryErrKind::BadColor)),
+            };
+            let fg = color()?;
+            let bg = color()?;
+            if parts.next().is_some() {
+                return Err(err(GlyphRegistryErrKind::TrailingText));
+            }
+
+            registry.insert(name, GlyphDef { glyph, fg, bg });
+        }
+        Ok(registry)
+    }
+
+    /// Names the glyph, replacing any previous glyph with that name
+    pub fn insert(&mut self, name: &str, def: GlyphDef) {
+        self.glyphs.insert(name.to_owned(), def);
+    }
+
+    /// The named glyph and its default colors
+    pub fn get(&self, name: &str) -> Option<&GlyphDef> {
+        self.glyphs.get(name)
+    }
+
+    /// The named glyph
+    pub fn glyph(&self, name: &str) -> Option<Glyph> {
+        self.get(name).map(|def| def.glyph)
+    }
+
+    /// The number of names
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Returns true if there are no names
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// The names in the registry
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.glyphs.keys().map(|name| name.as_str())
+    }
+}
+
+/// The kinds of errors when parsing a glyph manifest
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GlyphRegistryErrKind {
+    /// The line does not have a '=' after the name
+    MissingEquals,
+    /// There is nothing before the '='
+    MissingName,
+    /// The glyph is not a number
+    BadGlyph,
+    /// A color is not a color
+    BadColor,
+    /// There is more on the line after the colors
+    TrailingText,
+}
+
+/// An error found by [`GlyphRegistry::parse`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GlyphRegistryErr {
+    /// What went wrong
+    pub kind: GlyphRegistryErrKind,
+    /// The index of the line with the error
+    pub line: usize,
+}
+
+impl std::fmt::Display for GlyphRegistryErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self.kind {
+            GlyphRegistryErrKind::MissingEquals => "missing '=' after the name",
+            GlyphRegistryErrKind::MissingName => "missing name",
+            GlyphRegistryErrKind::BadGlyph => "glyph is not a number",
+            GlyphRegistryErrKind::BadColor => "bad color",
+            GlyphRegistryErrKind::TrailingText => "unexpected text after the colors",
+        };
+        write!(f, "{} at line {}", msg, self.line)
+    }
+}
+
+impl std::error::Error for GlyphRegistryErr {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let text = "# dungeon\nwall.stone = 0xDB #888 #222\n\nfloor=250 #444\ndoor = 43 - #630\nplayer = 64";
+        let registry = GlyphRegistry::parse(text).unwrap();
+        assert_eq!(registry.len(), 4);
+        assert_eq!(
+            registry.get("wall.stone"),
+            Some(&GlyphDef::new(219).fg("#888".into()).bg("#222".into()))
+        );
+        assert_eq!(registry.glyph("floor"), Some(250));
+        assert_eq!(registry.get("floor").unwrap().bg, None);
+        assert_eq!(registry.get("door").unwrap().fg, None);
+        assert_eq!(registry.get("door").unwrap().bg, Some("#630".into()));
+        assert_eq!(registry.get("player"), Some(&GlyphDef::new(64)));
+        assert_eq!(registry.glyph("taco"), None);
+
+        let err = GlyphRegistry::parse("a = 1\nb 2").unwrap_err();
+        assert_eq!(err.kind, GlyphRegistryErrKind::MissingEquals);
+        assert_eq!(err.line, 1);
+        let err = GlyphRegistry::parse(" = 2").unwrap_err();
+        assert_eq!(err.kind, GlyphRegistryErrKind::MissingName);
+        let err = GlyphRegistry::parse("a = x2").unwrap_err();
+        assert_eq!(err.kind, GlyphRegistryErrKind::BadGlyph);
+        let err = GlyphRegistry::parse("a = 2 #nope").unwrap_err();
+        assert_eq!(err.kind, GlyphRegistryErrKind::BadColor);
+        let err = GlyphRegistry::parse("door = 43 - #630 junk").unwrap_err();
+        assert_eq!(err.kind, GlyphRegistryErrKind::TrailingText);
+    }
+}
@@ -1,33 +1,43 @@
 mod app;
+mod atlas;
+mod bitmap_font;
 mod builder;
+mod codepage;
 pub mod codepage437;
 mod context;
 pub mod draw;
 mod file;
 mod font;
+mod glyphs;
 mod img;
 mod input;
 mod load_screen;
 mod rgba;
 mod runner;
 mod screen;
+mod sheet;
 mod simple;
 pub mod text;
 
 pub use app::{
     now, perf_now, App, AppConfig, AppEvent, KeyEvent, MouseButtonEvent, VirtualKeyCode,
 };
+pub use atlas::{AtlasBuilder, AtlasTable, GlyphRange};
+pub use bitmap_font::BitmapFont;
 pub use builder::*;
+pub use codepage::{Codepage, CodepageErr, CodepageErrKind};
 pub use context::*;
-pub use draw::{BorderType, TextAlign};
+pub use draw::{BorderType, TextAlign, VerticalAlign};
 pub use file::*;
-pub use font::Font;
+pub use font::{Font, FontErr, FontInfo, FontTransparency, GlyphAnimation, MAX_ANIMATIONS};
+pub use glyphs::{GlyphDef, GlyphRegistry, GlyphRegistryErr, GlyphRegistryErrKind};
 pub use img::*;
 pub use input::AppInput;
 pub use load_screen::*;
 pub use rgba::*;
 pub use runner::*;
 pub use screen::*;
+pub use sheet::*;
 pub use simple::*;
 
 pub fn console<T: AsRef<str>>(msg: T) {
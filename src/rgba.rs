@@ -120,6 +120,13 @@ impl RGBA {
         dr * dr + dg * dg + db * db
     }
 
+    /// Perceived brightness of the R,G,B components (0-255)
+    pub fn luminance(&self) -> u8 {
+        (0.299 * f32::from(self.0) + 0.587 * f32::from(self.1) + 0.114 * f32::from(self.2))
+            .round()
+            .clamp(0.0, 255.0) as u8
+    }
+
     /// Removes pct of color (RGB) from c1
     pub fn darken(c1: RGBA, pct: f32) -> RGBA {
         let mut to_sub = c1 * pct;
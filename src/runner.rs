@@ -107,6 +107,7 @@ impl Runner {
         ctx.resize(
             (real_screen_width as f32 / hidpi_factor) as u32,
             (real_screen_height as f32 / hidpi_factor) as u32,
+            self.real_screen_size,
         );
 
         // engine.resize(&mut self.api);
@@ -275,14 +276,25 @@ impl Runner {
     }
 
     fn do_startup_files(&mut self, ctx: &mut AppContext) {
-        for font in self.builder.fonts.drain(..) {
-            ctx.load_font(&font).expect("Failed to load font.");
+        // the errors are reported like the ones from the files that load later (see AppContext::load_files)
+        for (font, info) in self.builder.fonts.drain(..) {
+            let result = match info {
+                None => ctx.load_font(&font),
+                Some(info) => ctx.load_font_with_info(&font, &info),
+            };
+            if let Err(e) = result {
+                println!("Failed to load font({}) - {:?}", font, e);
+            }
         }
         for image in self.builder.images.drain(..) {
-            ctx.load_image(&image).expect("Failed to load image.");
+            if let Err(e) = ctx.load_image(&image) {
+                println!("Failed to load image({}) - {:?}", image, e);
+            }
         }
         for (path, func) in self.builder.files.drain(..) {
-            ctx.load_file(&path, func).expect("Failed to load file.");
+            if let Err(e) = ctx.load_file(&path, func) {
+                println!("Failed to load file({}) - {:?}", path, e);
+            }
         }
     }
 
@@ -498,7 +510,13 @@ fn create_ctx(app: &App, options: &AppConfig) -> AppContext {
         )
     };
 
-    AppContext::new(gl, options.size.clone(), input, options.fps)
+    AppContext::new(
+        gl,
+        options.size.clone(),
+        (real_screen_width, real_screen_height),
+        input,
+        options.fps,
+    )
 }
 
 /// This captures an in-game screenshot and saves it to the file
@@ -0,0 +1,164 @@
+use crate::Image;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A named sequence of sprite sheet frames, each shown for a number of milliseconds
+#[derive(Debug, Clone)]
+pub struct Animation {
+    frames: Vec<(usize, f64)>,
+    looping: bool,
+}
+
+impl Animation {
+    /// Constructs an animation from (frame index, duration ms) pairs
+    pub fn new(frames: &[(usize, f64)]) -> Self {
+        Animation {
+            frames: frames.to_vec(),
+            looping: true,
+        }
+    }
+
+    /// Sets whether the animation starts over at the end (default=true) or stays on the last frame
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Returns true if the animation starts over at the end
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// The total time of one pass through the animation in ms
+    pub fn duration(&self) -> f64 {
+        self.frames.iter().map(|(_, ms)| ms).sum()
+    }
+
+    /// Returns true if a non-looping animation has run to its end
+    pub fn is_done(&self, elapsed_ms: f64) -> bool {
+        !self.looping && elapsed_ms >= self.duration()
+    }
+
+    /// Returns the sheet frame index to show after elapsed_ms
+    pub fn frame_at(&self, elapsed_ms: f64) -> usize {
+        let duration = self.duration();
+        if self.frames.is_empty() {
+            return 0;
+        }
+        if duration <= 0.0 {
+            return self.frames[0].0;
+        }
+
+        let mut time = match self.looping {
+            true => elapsed_ms.rem_euclid(duration),
+            false => elapsed_ms.clamp(0.0, duration),
+        };
+        for (frame, ms) in self.frames.iter() {
+            if time < *ms {
+                return *frame;
+            }
+            time -= ms;
+        }
+        self.frames.last().unwrap().0
+    }
+}
+
+/// An image that holds many frames, along with named animations of those frames
+///
+/// Frames are drawn with [`crate::draw::Blitter::blit_frame`] and [`crate::draw::Blitter::blit_frame_ex`]
+pub struct SpriteSheet {
+    image: Rc<Image>,
+    frames: Vec<(u32, u32, u32, u32)>,
+    animations: HashMap<String, Animation>,
+}
+
+impl SpriteSheet {
+    /// Constructs a sprite sheet with no frames
+    pub fn new(image: Rc<Image>) -> Self {
+        SpriteSheet {
+            image,
+            frames: Vec::new(),
+            animations: HashMap::new(),
+        }
+    }
+
+    /// Constructs a sprite sheet with a frame for every cell_width x cell_height cell in the image
+    ///
+    /// Frames are numbered left to right, top to bottom.
+    pub fn with_grid(image: Rc<Image>, cell_width: u32, cell_height: u32) -> Self {
+        let mut sheet = SpriteSheet::new(image);
+        if cell_width > 0 && cell_height > 0 {
+            let (width, height) = sheet.image.size();
+            for y in 0..height / cell_height {
+                for x in 0..width / cell_width {
+                    sheet.add_frame(x * cell_width, y * cell_height, cell_width, cell_height);
+                }
+            }
+        }
+        sheet
+    }
+
+    /// The image that holds the frames
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Adds a frame at the given area of the image, returns its index
+    pub fn add_frame(&mut self, x: u32, y: u32, width: u32, height: u32) -> usize {
+        self.frames.push((x, y, width, height));
+        self.frames.len() - 1
+    }
+
+    /// Returns the area of the image (x, y, width, height) for the frame
+    pub fn frame(&self, index: usize) -> Option<(u32, u32, u32, u32)> {
+        self.frames.get(index).copied()
+    }
+
+    /// The number of frames in the sheet
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Adds (or replaces) a named animation
+    pub fn add_animation(&mut self, name: &str, animation: Animation) {
+        self.animations.insert(name.to_owned(), animation);
+    }
+
+    /// Returns the named animation
+    pub fn animation(&self, name: &str) -> Option<&Animation> {
+        self.animations.get(name)
+    }
+
+    /// Returns the frame index to show for the named animation after elapsed_ms
+    pub fn animation_frame(&self, name: &str, elapsed_ms: f64) -> Option<usize> {
+        self.animations.get(name).map(|a| a.frame_at(elapsed_ms))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn grid_frames() {
+        let sheet = SpriteSheet::with_grid(Rc::new(Image::empty(12, 8)), 4, 4);
+        assert_eq!(sheet.frame_count(), 6);
+        assert_eq!(sheet.frame(0), Some((0, 0, 4, 4)));
+        assert_eq!(sheet.frame(4), Some((4, 4, 4, 4)));
+        assert_eq!(sheet.frame(6), None);
+    }
+
+    #[test]
+    fn animation_timing() {
+        let anim = Animation::new(&[(3, 100.0), (4, 50.0), (5, 100.0)]);
+        assert_eq!(anim.duration(), 250.0);
+        assert_eq!(anim.frame_at(0.0), 3);
+        assert_eq!(anim.frame_at(120.0), 4);
+        assert_eq!(anim.frame_at(150.0), 5);
+        assert_eq!(anim.frame_at(260.0), 3);
+
+        let once = anim.looping(false);
+        assert_eq!(once.frame_at(260.0), 5);
+        assert!(once.is_done(260.0));
+    }
+}
@@ -1,7 +1,39 @@
 use crate::rgba::RGBA;
+use crate::{Codepage, GlyphDef, GlyphRegistry};
+use std::rc::Rc;
 
 pub type Glyph = u32;
 
+// parses a glyph written as a decimal or 0x hex number (as in markup, codepages and manifests)
+pub(crate) fn parse_glyph_code(code: &str) -> Option<Glyph> {
+    match code.strip_prefix("0x") {
+        Some(hex) => Glyph::from_str_radix(hex, 16).ok(),
+        None => code.parse::<Glyph>().ok(),
+    }
+}
+
+/// Flag added to the glyph in the left cell of a double width glyph (e.g. CJK)
+///
+/// The glyph is stretched over this cell and the [`WIDE_CONTINUATION`] cell to its right.
+pub const WIDE_GLYPH: Glyph = 0x0100_0000;
+/// Flag added to the glyph in the right cell of a double width glyph
+pub const WIDE_CONTINUATION: Glyph = 0x0200_0000;
+/// The part of a glyph that is the index into the font (without the flags)
+pub const GLYPH_INDEX_MASK: Glyph = 0x00FF_FFFF;
+/// The number of fonts a cell can choose from, see [`Buffer::font`] and [`super::Console::with_font_slot`]
+pub const MAX_FONTS: u8 = 4;
+
+/// Transform flag that mirrors the glyph in a cell left to right, see [`Buffer::transform`]
+pub const FLIP_X: u8 = 0x01;
+/// Transform flag that mirrors the glyph in a cell top to bottom
+pub const FLIP_Y: u8 = 0x02;
+/// Transform flag that turns the glyph in a cell a quarter turn clockwise (after any flips)
+pub const ROTATE_90: u8 = 0x04;
+/// Transform flag that turns the glyph in a cell a half turn
+pub const ROTATE_180: u8 = 0x08;
+/// Transform flag that turns the glyph in a cell three quarter turns clockwise (a quarter turn counter clockwise)
+pub const ROTATE_270: u8 = 0x0C;
+
 // #[derive(Copy, Clone)]
 // pub enum TextAlign {
 //     Left,
@@ -27,6 +59,12 @@ pub struct Buffer {
     glyph: Vec<u32>,
     back: Vec<RGBA>,
     fore: Vec<RGBA>,
+    // the font slot of each cell (0 = the console's font)
+    font: Vec<u8>,
+    // the flip and rotate flags of each cell
+    transform: Vec<u8>,
+    codepage: Option<Rc<Codepage>>,
+    glyphs: Option<Rc<GlyphRegistry>>,
     // colors: HashMap<String, RGBA>,
     // color_stack: Vec<RGBA>,
 }
@@ -38,6 +76,8 @@ impl Buffer {
         let mut back = Vec::new();
         let mut fore = Vec::new();
         let mut glyph = Vec::new();
+        let mut font = Vec::new();
+        let mut transform = Vec::new();
         let mut pot_width = 1;
         let mut pot_height = 1;
         while pot_width < width {
@@ -50,6 +90,8 @@ impl Buffer {
             back.push(RGBA::rgba(0, 0, 0, 0));
             fore.push(RGBA::rgba(255, 255, 255, 0));
             glyph.push(0);
+            font.push(0);
+            transform.push(0);
         }
 
         Self {
@@ -58,8 +100,12 @@ impl Buffer {
             glyph,
             back,
             fore,
+            font,
+            transform,
             pot_width,
             pot_height,
+            codepage: None,
+            glyphs: None,
             // colors: HashMap::new(),
             // color_stack: Vec::new(),
         }
@@ -81,6 +127,47 @@ impl Buffer {
         (self.pot_width, self.pot_height)
     }
 
+    /// The mapping of chars to glyphs for the font this buffer is drawn with (if any)
+    pub fn codepage(&self) -> Option<&Codepage> {
+        self.codepage.as_deref()
+    }
+
+    /// Sets the mapping of chars to glyphs, a [`super::Console`] sets this from its font
+    pub fn set_codepage(&mut self, codepage: Option<Rc<Codepage>>) {
+        self.codepage = codepage;
+    }
+
+    /// Converts the char to a glyph using the codepage, or (ch as u32) if there is no codepage
+    pub fn to_glyph(&self, ch: char) -> Glyph {
+        match self.codepage {
+            None => ch as u32,
+            Some(ref codepage) => codepage.to_glyph(ch),
+        }
+    }
+
+    /// The glyph for the char - using the function if there is one, otherwise the codepage (see [`Buffer::to_glyph`])
+    pub fn glyph_with(&self, to_glyph: Option<&dyn Fn(char) -> Glyph>, ch: char) -> Glyph {
+        match to_glyph {
+            Some(to_glyph) => to_glyph(ch),
+            None => self.to_glyph(ch),
+        }
+    }
+
+    /// The names of the glyphs of the font this buffer is drawn with (if any)
+    pub fn glyph_registry(&self) -> Option<&GlyphRegistry> {
+        self.glyphs.as_deref()
+    }
+
+    /// Sets the names of the glyphs, a [`super::Console`] sets this from its font
+    pub fn set_glyph_registry(&mut self, glyphs: Option<Rc<GlyphRegistry>>) {
+        self.glyphs = glyphs;
+    }
+
+    /// The named glyph and its default colors (if there is a glyph registry with the name)
+    pub fn glyph_def(&self, name: &str) -> Option<&GlyphDef> {
+        self.glyphs.as_ref().and_then(|glyphs| glyphs.get(name))
+    }
+
     /// resizes the console
     pub fn resize(&mut self, width: u32, height: u32) {
         self.width = width;
@@ -98,10 +185,14 @@ impl Buffer {
         self.back.clear();
         self.fore.clear();
         self.glyph.clear();
+        self.font.clear();
+        self.transform.clear();
         for _ in 0..(pot_width * pot_height) as usize {
             self.back.push(RGBA::rgba(0, 0, 0, 255));
             self.fore.push(RGBA::rgba(255, 255, 255, 255));
             self.glyph.push(' ' as u32);
+            self.font.push(0);
+            self.transform.push(0);
         }
     }
 
@@ -117,6 +208,14 @@ impl Buffer {
     pub fn backgrounds(&self) -> &Vec<RGBA> {
         &self.back
     }
+    /// for fast reading of the font slots
+    pub fn fonts(&self) -> &Vec<u8> {
+        &self.font
+    }
+    /// for fast reading of the transform flags
+    pub fn transforms(&self) -> &Vec<u8> {
+        &self.transform
+    }
     /// for fast writing of the characters values
     pub(crate) fn glyphs_mut(&mut self) -> &mut Vec<u32> {
         &mut self.glyph
@@ -152,6 +251,22 @@ impl Buffer {
         }
     }
 
+    /// get the font slot of a cell (if x,y inside the console)
+    pub fn get_font(&self, x: i32, y: i32) -> Option<&u8> {
+        match self.to_idx(x, y) {
+            None => None,
+            Some(idx) => self.font.get(idx),
+        }
+    }
+
+    /// get the transform flags of a cell (if x,y inside the console)
+    pub fn get_transform(&self, x: i32, y: i32) -> Option<&u8> {
+        match self.to_idx(x, y) {
+            None => None,
+            Some(idx) => self.transform.get(idx),
+        }
+    }
+
     fn to_idx(&self, x: i32, y: i32) -> Option<usize> {
         if x < 0 || x >= self.width() as i32 || y < 0 || y >= self.height() as i32 {
             return None;
@@ -170,6 +285,8 @@ impl Buffer {
     pub fn glyph(&mut self, x: i32, y: i32, glyph: Glyph) {
         if let Some(idx) = self.to_idx(x, y) {
             self.glyph[idx] = glyph;
+            self.font[idx] = 0;
+            self.transform[idx] = 0;
         }
     }
     /// set the character color at a specific position
@@ -184,6 +301,47 @@ impl Buffer {
             self.back[idx] = col;
         }
     }
+    /// set the font slot (0 = the console's font, up to [`MAX_FONTS`]) that draws the glyph at a specific position
+    ///
+    /// Drawing a new glyph in the cell puts it back in slot 0 (see [`Buffer::draw_opt_font`]), so set the slot after the glyph.
+    pub fn font(&mut self, x: i32, y: i32, font: u8) {
+        if let Some(idx) = self.to_idx(x, y) {
+            self.font[idx] = font;
+        }
+    }
+    /// set the font slot of an area
+    pub fn font_area(&mut self, x: i32, y: i32, w: u32, h: u32, font: u8) {
+        let right = x + (w as i32);
+        let down = y + (h as i32);
+        for iy in y.max(0)..down.min(self.height() as i32) {
+            let off = iy * self.pot_size().0 as i32;
+            for ix in x.max(0)..right.min(self.width() as i32) {
+                self.font[(off + ix) as usize] = font;
+            }
+        }
+    }
+    /// set the transform flags ([`FLIP_X`], [`FLIP_Y`] and one of the rotations, e.g. [`ROTATE_90`]) of the glyph at a specific position
+    ///
+    /// The glyph is flipped, then rotated.  0 draws the glyph as it is in the font.
+    /// Drawing a new glyph in the cell clears the flags, so set them after the glyph.
+    /// A double width glyph (see [`WIDE_GLYPH`]) is flipped as a whole when both of its cells have the flip,
+    /// and is not rotated.
+    pub fn transform(&mut self, x: i32, y: i32, flags: u8) {
+        if let Some(idx) = self.to_idx(x, y) {
+            self.transform[idx] = flags;
+        }
+    }
+    /// set the transform flags of an area
+    pub fn transform_area(&mut self, x: i32, y: i32, w: u32, h: u32, flags: u8) {
+        let right = x + (w as i32);
+        let down = y + (h as i32);
+        for iy in y.max(0)..down.min(self.height() as i32) {
+            let off = iy * self.pot_size().0 as i32;
+            for ix in x.max(0)..right.min(self.width() as i32) {
+                self.transform[(off + ix) as usize] = flags;
+            }
+        }
+    }
 
     pub fn update<F>(&mut self, func: F)
     where
@@ -390,6 +548,8 @@ impl Buffer {
     // }
 
     /// fill an area with values
+    ///
+    /// Filling the glyphs puts them back in the console's font (slot 0) and clears their transform flags.
     pub fn area(
         &mut self,
         x: i32,
@@ -407,6 +567,9 @@ impl Buffer {
                 let off = iy * self.pot_size().0 as i32;
                 for ix in x.max(0)..right.min(self.width() as i32) {
                     self.glyphs_mut()[(off + ix) as usize] = u32::from(fillchar);
+                    // the new glyph uses the console's font, and is not flipped or turned
+                    self.font[(off + ix) as usize] = 0;
+                    self.transform[(off + ix) as usize] = 0;
                 }
             }
         }
@@ -432,6 +595,8 @@ impl Buffer {
     pub fn draw(&mut self, x: i32, y: i32, glyph: Glyph, fore: RGBA, back: RGBA) {
         if let Some(idx) = self.to_idx(x, y) {
             self.glyph[idx] = glyph;
+            self.font[idx] = 0;
+            self.transform[idx] = 0;
             self.fore[idx] = fore;
             self.back[idx] = back;
         }
@@ -449,6 +614,8 @@ impl Buffer {
         if let Some(idx) = self.to_idx(x, y) {
             if let Some(code) = glyph {
                 self.glyph[idx] = code;
+                self.font[idx] = 0;
+                self.transform[idx] = 0;
             }
             if let Some(fore) = fore {
                 self.fore[idx] = fore;
@@ -458,6 +625,21 @@ impl Buffer {
             }
         }
     }
+    /// draws the cell like [`Buffer::draw_opt`], and sets its font slot (if there is one)
+    pub fn draw_opt_font(
+        &mut self,
+        x: i32,
+        y: i32,
+        glyph: Option<Glyph>,
+        fore: Option<RGBA>,
+        back: Option<RGBA>,
+        font: Option<u8>,
+    ) {
+        self.draw_opt(x, y, glyph, fore, back);
+        if let Some(font) = font {
+            self.font(x, y, font);
+        }
+    }
     /// blit (draw) a console onto another one
     /// You can use fore_alpha and back_alpha to blend this console with existing background on the destination.
     /// If you define a key color, the cells using this color as background will be ignored. This makes it possible to blit
@@ -532,9 +714,15 @@ impl Buffer {
                                     RGBA::blend(dst_fore, src_back, back_alpha);
                             } else if dst_char == ' ' as u32 || dst_char == 0 {
                                 destination.glyphs_mut()[dest_idx] = src_char;
+                                destination.font[dest_idx] = self.font[src_idx];
+                                destination.transform[dest_idx] = self.transform[src_idx];
                                 destination.foregrounds_mut()[dest_idx] =
                                     RGBA::blend(dst_back, src_fore, fore_alpha);
-                            } else if dst_char == src_char {
+                            } else if dst_char == src_char
+                                && destination.font[dest_idx] == self.font[src_idx]
+                                && destination.transform[dest_idx] == self.transform[src_idx]
+                            {
+                                // the same glyph, drawn the same way
                                 destination.foregrounds_mut()[dest_idx] =
                                     RGBA::blend(dst_fore, src_fore, fore_alpha);
                             } else if fore_alpha < 0.5 {
@@ -542,12 +730,16 @@ impl Buffer {
                                     RGBA::blend(dst_fore, dst_back, fore_alpha * 2.0);
                             } else {
                                 destination.glyphs_mut()[dest_idx] = src_char;
+                                destination.font[dest_idx] = self.font[src_idx];
+                                destination.transform[dest_idx] = self.transform[src_idx];
                                 destination.foregrounds_mut()[dest_idx] =
                                     RGBA::blend(dst_back, src_fore, (fore_alpha - 0.5) * 2.0);
                             }
                         } else {
                             destination.foregrounds_mut()[dest_idx] = src_fore;
                             destination.glyphs_mut()[dest_idx] = src_char;
+                            destination.font[dest_idx] = self.font[src_idx];
+                            destination.transform[dest_idx] = self.transform[src_idx];
                         }
                     }
                 }
@@ -584,3 +776,117 @@ fn text_color_len(text: &str) -> usize {
     }
     text_len
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn transform() {
+        let mut buffer = Buffer::new(10, 5);
+        buffer.transform_area(0, 0, 3, 2, FLIP_X);
+        buffer.draw(
+            1,
+            1,
+            '>' as Glyph,
+            RGBA::rgb(255, 255, 255),
+            RGBA::rgb(0, 0, 0),
+        );
+        buffer.transform(1, 1, FLIP_Y | ROTATE_90);
+        buffer.transform(20, 1, ROTATE_180);
+        assert_eq!(buffer.get_transform(0, 0), Some(&FLIP_X));
+        assert_eq!(buffer.get_transform(1, 1), Some(&(FLIP_Y | ROTATE_90)));
+        assert_eq!(buffer.get_transform(3, 0), Some(&0));
+        assert_eq!(buffer.get_transform(20, 1), None);
+
+        // blit copies the flags with the glyphs
+        let mut dest = Buffer::new(10, 5);
+        buffer.blit(2, 2, &mut dest, 1.0, 1.0, None);
+        assert_eq!(dest.get_transform(3, 3), Some(&(FLIP_Y | ROTATE_90)));
+        assert_eq!(dest.get_transform(2, 2), Some(&FLIP_X));
+
+        dest.resize(4, 4);
+        assert_eq!(dest.get_transform(3, 3), Some(&0));
+
+        // the same glyph in another font or turned another way is a different glyph
+        let (white, black) = (RGBA::rgb(255, 255, 255), RGBA::rgb(0, 0, 0));
+        let mut src = Buffer::new(2, 1);
+        src.draw(0, 0, 1, white, black);
+        src.transform(0, 0, FLIP_X);
+        src.font(0, 0, 1);
+        src.draw(1, 0, 1, white, black);
+        let mut dest = Buffer::new(2, 1);
+        dest.fill(Some(1), Some(black), Some(black));
+        src.blit(0, 0, &mut dest, 0.75, 1.0, None);
+        assert_eq!(dest.get_font(0, 0), Some(&1));
+        assert_eq!(dest.get_transform(0, 0), Some(&FLIP_X));
+        // the glyph is the same, so the colors are blended
+        assert_eq!(dest.get_fore(1, 0), Some(&RGBA::blend(black, white, 0.75)));
+        assert_eq!(dest.get_fore(0, 0), Some(&RGBA::blend(black, white, 0.5)));
+    }
+
+    #[test]
+    fn transform_redraw() {
+        // a conveyor scrolls by one cell - the flags move with the glyphs
+        let mut buffer = Buffer::new(10, 5);
+        let (white, black) = (RGBA::rgb(255, 255, 255), RGBA::rgb(0, 0, 0));
+        for x in 2..5 {
+            buffer.draw(x, 1, '>' as Glyph, white, black);
+            buffer.transform(x, 1, ROTATE_90);
+        }
+        buffer.draw(7, 1, '!' as Glyph, white, black);
+
+        buffer.clear(true, true, true);
+        assert!(buffer.transforms().iter().all(|&flags| flags == 0));
+        for x in 3..6 {
+            buffer.draw(x, 1, '>' as Glyph, white, black);
+            buffer.transform(x, 1, ROTATE_90);
+        }
+        buffer.draw(2, 1, '.' as Glyph, white, black);
+        assert_eq!(buffer.get_transform(2, 1), Some(&0));
+        assert_eq!(buffer.get_transform(5, 1), Some(&ROTATE_90));
+
+        // a new glyph is not turned like the old one
+        buffer.glyph(3, 1, 'x' as Glyph);
+        assert_eq!(buffer.get_transform(3, 1), Some(&0));
+        buffer.draw_opt(4, 1, Some('y' as Glyph), None, None);
+        assert_eq!(buffer.get_transform(4, 1), Some(&0));
+        buffer.draw_opt(5, 1, None, Some(white), None);
+        assert_eq!(buffer.get_transform(5, 1), Some(&ROTATE_90));
+    }
+
+    #[test]
+    fn font_slots() {
+        let mut buffer = Buffer::new(10, 5);
+        buffer.font_area(0, 0, 4, 4, 2);
+        buffer.font(6, 1, 1);
+
+        // only the cells that get a new glyph go back to the console's font
+        buffer.area(0, 0, 2, 2, None, Some(RGBA::rgb(255, 0, 0)), None);
+        assert_eq!(buffer.get_font(1, 1), Some(&2));
+        buffer.area(0, 0, 2, 2, Some('#' as Glyph), None, None);
+        assert_eq!(buffer.get_font(1, 1), Some(&0));
+        assert_eq!(buffer.get_font(3, 3), Some(&2));
+
+        buffer.clear(true, true, true);
+        assert_eq!(buffer.get_font(3, 3), Some(&0));
+        assert_eq!(buffer.get_font(6, 1), Some(&0));
+
+        // drawing over a slot 2 cell without a font uses the console's font
+        let (white, black) = (RGBA::rgb(255, 255, 255), RGBA::rgb(0, 0, 0));
+        buffer.draw_opt_font(1, 1, Some('a' as Glyph), None, None, Some(2));
+        assert_eq!(buffer.get_font(1, 1), Some(&2));
+        buffer.draw(1, 1, 'b' as Glyph, white, black);
+        assert_eq!(buffer.get_font(1, 1), Some(&0));
+
+        buffer.draw_opt_font(2, 1, Some('a' as Glyph), None, None, Some(2));
+        buffer.glyph(2, 1, 'c' as Glyph);
+        assert_eq!(buffer.get_font(2, 1), Some(&0));
+
+        buffer.draw_opt_font(3, 1, Some('a' as Glyph), None, None, Some(2));
+        buffer.draw_opt(3, 1, None, Some(white), None);
+        assert_eq!(buffer.get_font(3, 1), Some(&2));
+        buffer.draw_opt(3, 1, Some('d' as Glyph), None, None);
+        assert_eq!(buffer.get_font(3, 1), Some(&0));
+    }
+}
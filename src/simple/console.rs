@@ -1,14 +1,98 @@
-use super::Buffer;
+use super::{Buffer, Sprite, SpriteLayout, MAX_FONTS};
 use crate::font::Font;
 use crate::{console, AppContext};
 use std::rc::Rc;
 
+/// How a console fills its extents on the screen, see [`Console::set_scale`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ScalePolicy {
+    /// The cells stretch to fill the extents (default)
+    #[default]
+    Stretch,
+    /// The cells keep the font's shape and are as large as fits, centered with bars on the other sides
+    Fit,
+    /// The cells are a whole multiple of the font's pixel size, as large as fits and centered - no blur or shimmer
+    Integer,
+    /// The font is drawn at the (whole) scale and the console is resized to fill its extents with cells
+    Grow(u32),
+}
+
+impl ScalePolicy {
+    /// The extents to draw the cells in and the number of cells to show, for a console with the given area
+    /// (the extents it was given), screen size in pixels, font cell size in pixels, and number of cells
+    pub fn layout(
+        &self,
+        area: (f32, f32, f32, f32),
+        screen_size: (u32, u32),
+        char_size: (u32, u32),
+        cells: (u32, u32),
+    ) -> ((f32, f32, f32, f32), (u32, u32)) {
+        if *self == ScalePolicy::Stretch
+            || screen_size.0 == 0
+            || screen_size.1 == 0
+            || char_size.0 == 0
+            || char_size.1 == 0
+        {
+            return (area, cells);
+        }
+        let (screen_width, screen_height) = (screen_size.0 as f32, screen_size.1 as f32);
+        let left = area.0 * screen_width;
+        let top = area.1 * screen_height;
+        let area_width = (area.2 - area.0) * screen_width;
+        let area_height = (area.3 - area.1) * screen_height;
+
+        let (cells, scale) = match *self {
+            ScalePolicy::Grow(scale) => {
+                let scale = scale.max(1);
+                let cells = (
+                    ((area_width / (char_size.0 * scale) as f32) as u32).max(1),
+                    ((area_height / (char_size.1 * scale) as f32) as u32).max(1),
+                );
+                (cells, scale as f32)
+            }
+            _ => {
+                let scale = (area_width / (cells.0 * char_size.0).max(1) as f32)
+                    .min(area_height / (cells.1 * char_size.1).max(1) as f32);
+                match *self {
+                    ScalePolicy::Integer => (cells, scale.floor().max(1.0)),
+                    _ => (cells, scale),
+                }
+            }
+        };
+
+        // whole pixels, so the cells line up with the screen
+        let width = (cells.0 * char_size.0) as f32 * scale;
+        let height = (cells.1 * char_size.1) as f32 * scale;
+        let left = (left + (area_width - width) / 2.0).floor();
+        let top = (top + (area_height - height) / 2.0).floor();
+        // the cells never spill out of the area (e.g. an area smaller than the font)
+        let extents = (
+            (left / screen_width).max(area.0),
+            (top / screen_height).max(area.1),
+            ((left + width) / screen_width).min(area.2),
+            ((top + height) / screen_height).min(area.3),
+        );
+        (extents, cells)
+    }
+}
+
 /// This contains the data for a console (including the one displayed on the screen) and methods to draw on it.
 pub struct Console {
     buffer: Buffer,
     extents: (f32, f32, f32, f32),
+    // the extents that were set, the cells are drawn in them as the scale says
+    area: (f32, f32, f32, f32),
+    scale: ScalePolicy,
+    // the screen size, char size and view size the extents were last fit to
+    scaled_for: Option<[(u32, u32); 3]>,
     font_name: String,
     font: Option<Rc<Font>>,
+    // the fonts for slots 1.. (see Buffer::font), "" = unused
+    slot_fonts: Vec<(String, Option<Rc<Font>>)>,
+    // drawn over the cells
+    sprites: Vec<Sprite>,
+    // the fractional cell shown at the top left (None = no camera, all of the cells are shown)
+    camera_offset: Option<(f32, f32)>,
     zpos: i8,
 }
 
@@ -19,8 +103,14 @@ impl Console {
         Self {
             buffer: Buffer::new(width, height),
             extents: (0.0, 0.0, 1.0, 1.0),
+            area: (0.0, 0.0, 1.0, 1.0),
+            scale: ScalePolicy::Stretch,
+            scaled_for: None,
             font_name: font_name.to_owned(),
             font: None,
+            slot_fonts: vec![(String::new(), None); MAX_FONTS as usize - 1],
+            sprites: Vec::new(),
+            camera_offset: None,
             zpos: 0,
         }
     }
@@ -34,13 +124,70 @@ impl Console {
         println!("console extents = {},{} - {},{}", left, top, right, bottom);
 
         self.extents = (left, top, right, bottom);
+        self.area = self.extents;
+        self.scaled_for = None;
         self
     }
 
+    /// The part of the screen the cells are drawn in - inside the extents that were set, depending on the scale
     pub fn extents(&self) -> &(f32, f32, f32, f32) {
         &self.extents
     }
 
+    /// Sets how the console fills its extents, see [`Console::set_scale`]
+    pub fn with_scale(mut self, scale: ScalePolicy) -> Self {
+        self.set_scale(scale);
+        self
+    }
+
+    /// Sets how the console fills its extents (default=[`ScalePolicy::Stretch`])
+    ///
+    /// The console fits its drawn extents to the screen when it renders, whenever the screen size, font or
+    /// console size has changed.  With [`ScalePolicy::Grow`] the console is resized, so draw to the new size -
+    /// call [`Console::fit_to_screen`] from [`crate::Screen::resize`] to get the new size before the next render.
+    pub fn set_scale(&mut self, scale: ScalePolicy) -> &mut Self {
+        self.scale = scale;
+        self.extents = self.area;
+        self.scaled_for = None;
+        self
+    }
+
+    /// How the console fills its extents
+    pub fn scale(&self) -> ScalePolicy {
+        self.scale
+    }
+
+    /// Fits the drawn extents (and the size, for [`ScalePolicy::Grow`]) to the screen
+    pub fn fit_to_screen(&mut self, app: &AppContext) {
+        let char_size = match self.font {
+            Some(ref font) => font.char_size(),
+            None => match app.get_font(&self.font_name) {
+                Some(font) => font.char_size(),
+                None => return,
+            },
+        };
+        self.apply_scale(app.real_screen_size, char_size);
+    }
+
+    // fits the extents to the screen, if anything changed since the last time
+    fn apply_scale(&mut self, screen_size: (u32, u32), char_size: (u32, u32)) {
+        let key = [screen_size, char_size, self.view_size()];
+        if self.scaled_for == Some(key) {
+            return;
+        }
+        let (extents, cells) = self.scale.layout(self.area, screen_size, char_size, key[2]);
+        self.extents = extents;
+        if cells != key[2] {
+            // the camera shows one less column and row than the buffer has
+            let extra = match self.camera_offset {
+                None => 0,
+                Some(_) => 1,
+            };
+            self.buffer.resize(cells.0 + extra, cells.1 + extra);
+        }
+        self.scaled_for = Some([screen_size, char_size, self.view_size()]);
+    }
+
     pub fn is_full_screen(&self) -> bool {
         self.extents.0 == 0.0
             && self.extents.1 == 0.0
@@ -48,6 +195,55 @@ impl Console {
             && self.extents.3 == 1.0
     }
 
+    /// Turns on the camera and shows the cells from the offset, see [`Console::set_camera_offset`]
+    pub fn with_camera_offset(mut self, x: f32, y: f32) -> Self {
+        self.set_camera_offset(x, y);
+        self
+    }
+
+    /// Turns on the camera and shows the cells from the offset - (0.5, 0.0) starts half way into the first column
+    ///
+    /// With the camera, the console shows one less column and row than the buffer has (see [`Console::view_size`]),
+    /// so make the buffer one cell wider and taller than the view.  The offset is from 0 to 1 in each direction,
+    /// and moving it gives pixel smooth scrolling (see [`Console::follow`]).
+    pub fn set_camera_offset(&mut self, x: f32, y: f32) -> &mut Self {
+        self.camera_offset = Some((x.clamp(0.0, 1.0), y.clamp(0.0, 1.0)));
+        self
+    }
+
+    /// Turns off the camera, so all of the cells are shown
+    pub fn clear_camera_offset(&mut self) -> &mut Self {
+        self.camera_offset = None;
+        self
+    }
+
+    /// The camera offset (if the camera is on)
+    pub fn camera_offset(&self) -> Option<(f32, f32)> {
+        self.camera_offset
+    }
+
+    /// The number of cells shown - the size of the buffer, less one column and row with the camera on
+    pub fn view_size(&self) -> (u32, u32) {
+        let (width, height) = self.buffer.size();
+        match self.camera_offset {
+            None => (width, height),
+            Some(_) => (width.saturating_sub(1), height.saturating_sub(1)),
+        }
+    }
+
+    /// Points the camera at the (fractional) map position, so it is in the center of the view
+    ///
+    /// Returns the map cell to draw in the top left cell of the buffer - draw the map from there to fill the buffer.
+    pub fn follow(&mut self, x: f32, y: f32) -> (i32, i32) {
+        let (width, height) = self.buffer.size();
+        let (view_width, view_height) = (width.saturating_sub(1), height.saturating_sub(1));
+        let left = x - view_width as f32 / 2.0;
+        let top = y - view_height as f32 / 2.0;
+        let start = (left.floor(), top.floor());
+        self.set_camera_offset(left - start.0, top - start.1);
+        (start.0 as i32, start.1 as i32)
+    }
+
     pub fn with_zpos(mut self, zpos: i8) -> Self {
         self.zpos = zpos;
         self
@@ -67,9 +263,49 @@ impl Console {
     }
 
     pub fn set_font(&mut self, font: Rc<Font>) {
+        self.buffer.set_codepage(font.codepage());
+        self.buffer.set_glyph_registry(font.glyph_registry());
         self.font = Some(font);
     }
 
+    /// Sets the font for a font slot, so cells can use it (see [`Buffer::font`])
+    ///
+    /// Slot 0 is the console's font.  Cells whose slot has no font use the console's font.
+    /// Panics if the slot is not less than [`MAX_FONTS`].
+    pub fn with_font_slot(mut self, slot: u8, font_name: &str) -> Self {
+        self.set_font_slot(slot, font_name);
+        self
+    }
+
+    /// Sets the font for a font slot, see [`Console::with_font_slot`]
+    pub fn set_font_slot(&mut self, slot: u8, font_name: &str) -> &mut Self {
+        assert!(
+            slot < MAX_FONTS,
+            "font slot must be less than {}",
+            MAX_FONTS
+        );
+        match slot {
+            0 => {
+                self.font_name = font_name.to_owned();
+                self.font = None;
+            }
+            _ => self.slot_fonts[slot as usize - 1] = (font_name.to_owned(), None),
+        }
+        self
+    }
+
+    /// The name of the font in a font slot (if any)
+    pub fn font_slot_name(&self, slot: u8) -> Option<&str> {
+        match slot {
+            0 => Some(&self.font_name),
+            _ => self
+                .slot_fonts
+                .get(slot as usize - 1)
+                .map(|(name, _)| name.as_str())
+                .filter(|name| !name.is_empty()),
+        }
+    }
+
     pub fn buffer(&self) -> &Buffer {
         &self.buffer
     }
@@ -78,6 +314,27 @@ impl Console {
         &mut self.buffer
     }
 
+    /// The sprites drawn over the cells
+    pub fn sprites(&self) -> &Vec<Sprite> {
+        &self.sprites
+    }
+
+    /// The sprites drawn over the cells - to move, change or remove them
+    pub fn sprites_mut(&mut self) -> &mut Vec<Sprite> {
+        &mut self.sprites
+    }
+
+    /// Adds a sprite that is drawn over the cells (with the console's font) until it is removed, returns its index
+    pub fn add_sprite(&mut self, sprite: Sprite) -> usize {
+        self.sprites.push(sprite);
+        self.sprites.len() - 1
+    }
+
+    /// Removes all of the sprites
+    pub fn clear_sprites(&mut self) {
+        self.sprites.clear();
+    }
+
     pub fn width(&self) -> u32 {
         self.buffer.width()
     }
@@ -107,19 +364,54 @@ impl Console {
     }
 
     pub fn render(&mut self, app: &mut AppContext) {
+        // refit if the screen, font or console size changed (see Console::set_scale)
+        if let Some(char_size) = self.font.as_ref().map(|font| font.char_size()) {
+            self.apply_scale(app.real_screen_size, char_size);
+        }
         match self.font {
             None => {
                 self.font = app.get_font(self.font_name.as_ref());
-                if self.font.is_some() {
+                if let Some(ref font) = self.font {
+                    self.buffer.set_codepage(font.codepage());
+                    self.buffer.set_glyph_registry(font.glyph_registry());
                     console(format!("Got font - {}", self.font_name));
                 }
             }
             Some(ref font) => {
+                // the codepage and glyph names can be attached to the font at any time
+                self.buffer.set_codepage(font.codepage());
+                self.buffer.set_glyph_registry(font.glyph_registry());
+
+                for (name, slot_font) in self.slot_fonts.iter_mut() {
+                    if slot_font.is_none() && !name.is_empty() {
+                        *slot_font = app.get_font(name);
+                    }
+                }
+
+                let time = app.time();
                 let gl = &app.gl;
                 let program = &mut app.simple_program;
-                program.use_font(gl, &font);
+                program.use_font(gl, 0, font, time);
+                for (slot, (_, slot_font)) in self.slot_fonts.iter().enumerate() {
+                    program.use_font(gl, slot as u8 + 1, slot_font.as_ref().unwrap_or(font), time);
+                }
                 program.set_extents(gl, &self.extents, self.zpos);
-                program.render_buffer(gl, &self.buffer);
+                let offset = self.camera_offset.unwrap_or((0.0, 0.0));
+                let (view_width, view_height) = self.view_size();
+                let view_size = (view_width as f32, view_height as f32);
+                program.render_buffer_view(gl, &self.buffer, offset, view_size);
+
+                if !self.sprites.is_empty() {
+                    let layout = SpriteLayout {
+                        extents: self.extents,
+                        offset,
+                        cells: self.view_size(),
+                        char_size: font.char_size(),
+                        // the same pixels the extents were fit to
+                        screen_size: app.real_screen_size,
+                    };
+                    app.sprite_program.render(gl, font, &layout, &self.sprites);
+                }
 
                 // font.render(gl, &self.extents, &self.buffer);
             }
@@ -144,6 +436,8 @@ impl Console {
     }
 
     /// returns the cell that the screen pos converts to for this console [0.0-1.0]
+    ///
+    /// The cell includes the camera offset (if any), so it is the cell of the buffer under the mouse.
     pub fn mouse_pos(&self, screen_pct: (f32, f32)) -> Option<(f32, f32)> {
         if screen_pct.0 < self.extents.0 {
             return None;
@@ -163,9 +457,11 @@ impl Console {
             (screen_pct.1 - self.extents.1) / (self.extents.3 - self.extents.1),
         );
 
+        let (view_width, view_height) = self.view_size();
+        let offset = self.camera_offset.unwrap_or((0.0, 0.0));
         Some((
-            (cell_pct.0) * self.buffer.width() as f32,
-            (cell_pct.1) * self.buffer.height() as f32,
+            offset.0 + (cell_pct.0) * view_width as f32,
+            offset.1 + (cell_pct.1) * view_height as f32,
         ))
     }
 }
@@ -221,3 +517,83 @@ pub fn calc_center_extents(window_size_pct: (f32, f32)) -> (f32, f32, f32, f32)
         offset.1 + window_size_pct.1,
     )
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn camera() {
+        // a 20x10 view with a spare column and row for the scrolling
+        let mut con = Console::new(21, 11, "DEFAULT");
+        assert_eq!(con.view_size(), (21, 11));
+        assert_eq!(con.mouse_pos((0.5, 0.5)), Some((10.5, 5.5)));
+
+        let start = con.follow(30.25, 12.5);
+        assert_eq!(con.view_size(), (20, 10));
+        assert_eq!(start, (20, 7));
+        assert_eq!(con.camera_offset(), Some((0.25, 0.5)));
+
+        // the mouse is over the buffer cell under the camera
+        assert_eq!(con.mouse_pos((0.0, 0.0)), Some((0.25, 0.5)));
+        assert_eq!(con.mouse_pos((0.5, 0.5)), Some((10.25, 5.5)));
+
+        con.set_camera_offset(-1.0, 2.0);
+        assert_eq!(con.camera_offset(), Some((0.0, 1.0)));
+        con.clear_camera_offset();
+        assert_eq!(con.camera_offset(), None);
+        assert_eq!(con.view_size(), (21, 11));
+    }
+
+    fn close(a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 0.0001
+            && (a.1 - b.1).abs() < 0.0001
+            && (a.2 - b.2).abs() < 0.0001
+            && (a.3 - b.3).abs() < 0.0001
+    }
+
+    #[test]
+    fn scale_policy() {
+        // 40x25 cells of an 8x8 font (320x200 pixels) on an 800x600 screen
+        let full = (0.0, 0.0, 1.0, 1.0);
+        let layout = |policy: ScalePolicy| policy.layout(full, (800, 600), (8, 8), (40, 25));
+
+        assert_eq!(layout(ScalePolicy::Stretch), (full, (40, 25)));
+
+        // 2.5x fills the width, with bars at the top and bottom
+        let (extents, cells) = layout(ScalePolicy::Fit);
+        assert!(close(extents, (0.0, 50.0 / 600.0, 1.0, 550.0 / 600.0)));
+        assert_eq!(cells, (40, 25));
+
+        // 2x is the largest whole scale that fits
+        let (extents, cells) = layout(ScalePolicy::Integer);
+        assert!(close(extents, (0.1, 100.0 / 600.0, 0.9, 500.0 / 600.0)));
+        assert_eq!(cells, (40, 25));
+
+        // 16x16 cells, as many as fit
+        let (extents, cells) = layout(ScalePolicy::Grow(2));
+        assert!(close(extents, (0.0, 4.0 / 600.0, 1.0, 596.0 / 600.0)));
+        assert_eq!(cells, (50, 37));
+
+        // never smaller than the font, but kept inside the area
+        let (extents, _) = ScalePolicy::Integer.layout(full, (160, 100), (8, 8), (40, 25));
+        assert_eq!(extents, full);
+        let area = (0.25, 0.0, 0.75, 0.5);
+        let (extents, _) = ScalePolicy::Integer.layout(area, (160, 100), (8, 8), (40, 25));
+        assert_eq!(extents, area);
+
+        // the console grows, keeping the spare camera column and row
+        let mut con = Console::new(41, 26, "DEFAULT")
+            .with_camera_offset(0.0, 0.0)
+            .with_scale(ScalePolicy::Grow(2));
+        con.apply_scale((800, 600), (8, 8));
+        assert_eq!(con.size(), (51, 38));
+        assert_eq!(con.view_size(), (50, 37));
+        assert!(close(
+            *con.extents(),
+            (0.0, 4.0 / 600.0, 1.0, 596.0 / 600.0)
+        ));
+        con.set_scale(ScalePolicy::Stretch);
+        assert_eq!(*con.extents(), full);
+    }
+}
@@ -1,18 +1,64 @@
 precision mediump float;
-uniform sampler2D uFont; // font texture
+// the font textures - the console's font and the other font slots (see MAX_FONTS)
+uniform sampler2D uFont[4];
 uniform sampler2D uAscii; // console ascii code
 uniform sampler2D uFront; // console foreground color
 uniform sampler2D uBack; // console background color
-// used to convert ascii code into x,y character pos inside the font
-uniform float uFontCharsPerLine;
-// converts character pos inside font (0,0) - (16,16) into texture coord (0,0) - (1,1)
-uniform vec2 uFontCoef;
+uniform sampler2D uAttrs; // console cell attributes (r = font slot, g = transform flags)
+// used to convert ascii code into x,y character pos inside the font (for each font)
+uniform float uFontCharsPerLine[4];
+// converts character pos inside font (0,0) - (16,16) into texture coord (0,0) - (1,1) (for each font)
+uniform vec2 uFontCoef[4];
+// 1.0 = the glyphs keep their own colors, 0.0 = the glyphs are tinted with the foreground color (for each font)
+uniform float uFontFullColor[4];
+// the animated glyph ranges, 8 for each font - (first glyph, frames, position in the cycle 0-1, 1.0 = lockstep)
+// a range with 0 frames is not used
+uniform vec4 uFontAnims[32];
 // converts cell pos inside console (0,0) - (console_width,console_height) into texture coord (0,0) - (1,1)
 // the size of the console texture is not console_width x console_height but the closest power of 2 values
 uniform vec2 uTermCoef;
 
 in vec2 vTextureCoord;
 out vec4 FragColor;
+
+// a phase between 0 and 1 for the cell, so animations in neighbouring cells do not pulse together
+float cell_phase(vec2 cell) {
+    return fract(sin(dot(cell, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+// flips (1 = x, 2 = y) and then turns (4 = a quarter turn clockwise) the glyph - finds the pos in the glyph to draw at the pos in the cell
+vec2 transform_cell(vec2 pos, int flags) {
+    int turns = (flags >> 2) & 3;
+    vec2 glyphPos = pos;
+    if (turns == 1) {
+        glyphPos = vec2(pos.y, 1.0 - pos.x);
+    } else if (turns == 2) {
+        glyphPos = vec2(1.0 - pos.x, 1.0 - pos.y);
+    } else if (turns == 3) {
+        glyphPos = vec2(1.0 - pos.y, pos.x);
+    }
+    if ((flags & 1) != 0) {
+        glyphPos.x = 1.0 - glyphPos.x;
+    }
+    if ((flags & 2) != 0) {
+        glyphPos.y = 1.0 - glyphPos.y;
+    }
+    // stay inside the cell (1.0 is the first pixel of the next glyph)
+    return clamp(glyphPos, 0.0, 0.9999);
+}
+
+// samplers can only be indexed with constants
+vec4 font_texel(int font, vec2 coord) {
+    if (font == 1) {
+        return texture(uFont[1], coord);
+    } else if (font == 2) {
+        return texture(uFont[2], coord);
+    } else if (font == 3) {
+        return texture(uFont[3], coord);
+    }
+    return texture(uFont[0], coord);
+}
+
 void main() {
     // address = coordinate in the console textures (front, back, ascii) between (0,0) and (1,1)
     vec2 address = floor(vTextureCoord) * uTermCoef + vec2(0.001, 0.001);
@@ -23,10 +69,39 @@ void main() {
     // get the foreground and background colors
     vec4 foreground = texture(uFront, address);
     vec4 background = texture(uBack, address);
+    // the font slot of the cell (slots past the last font use the console's font)
+    vec4 attrs = texture(uAttrs, address);
+    int font = int(floor(attrs.r * 255.0 + 0.5));
+    if (font > 3) {
+        font = 0;
+    }
+    // cells with the first glyph of an animated range show the current frame
+    for (int i = 0; i < 8; i++) {
+        vec4 anim = uFontAnims[font * 8 + i];
+        if (anim.y > 0.0 && abs(ascii_code - anim.x) < 0.5) {
+            float phase = anim.w > 0.5 ? 0.0 : cell_phase(floor(vTextureCoord));
+            ascii_code += min(floor(fract(anim.z + phase) * anim.y), anim.y - 1.0);
+            break;
+        }
+    }
+    float charsPerLine = uFontCharsPerLine[font];
+    vec2 fontCoef = uFontCoef[font];
     // get coordinate of the glyph in the font texture for the ascii character
-    vec2 tchar = vec2(mod(floor(ascii_code), floor(uFontCharsPerLine)), floor(ascii_code / uFontCharsPerLine));
+    vec2 tchar = vec2(mod(floor(ascii_code), floor(charsPerLine)), floor(ascii_code / charsPerLine));
     // where are we inside the cell / glyph
-    vec2 pixPos = fract(vTextureCoord) * uFontCoef;
-    vec4 font_color = texture(uFont, tchar * uFontCoef + pixPos);
-    FragColor=font_color.a * foreground * vec4(font_color.rgb,1.0) + (1.0 - font_color.a) * background;
+    vec2 cellPos = fract(vTextureCoord);
+    int flags = int(floor(attrs.g * 255.0 + 0.5));
+    // the high byte has the flags for double width glyphs (1 = left half, 2 = right half)
+    float wide = floor(ascii_vec.a * 255.0 + 0.5);
+    if (wide == 1.0 || wide == 2.0) {
+        // the two cells draw one glyph, so the flips are across both of them (the halves swap) and turns are not used
+        cellPos.x = (wide == 1.0 ? 0.0 : 0.5) + cellPos.x * 0.5;
+        cellPos = transform_cell(cellPos, flags & 3);
+    } else {
+        cellPos = transform_cell(cellPos, flags);
+    }
+    vec2 pixPos = cellPos * fontCoef;
+    vec4 font_color = font_texel(font, tchar * fontCoef + pixPos);
+    vec4 tint = mix(foreground, vec4(1.0), uFontFullColor[font]);
+    FragColor=font_color.a * tint * vec4(font_color.rgb,1.0) + (1.0 - font_color.a) * background;
 }
@@ -1,12 +1,15 @@
 in vec2 aVertexPosition;
 in vec2 aTextureCoord;
 out vec2 vTextureCoord;
+// the number of cells shown
 uniform vec2 uTermSize;
+// the (fractional) cell shown at the top left, see Console::set_camera_offset
+uniform vec2 uTermOffset;
 // uniform float uZPos;
 void main(void) {
     // vertex position from (-1,-1) to (1,1)
     // gl_Position = vec4(aVertexPosition.xy, uZPos, 1.0);
     gl_Position = vec4(aVertexPosition.xy, 0.0, 1.0);
-    // texture coordinates from (0,0) to (console_width,console_height)
-    vTextureCoord = aTextureCoord * uTermSize;
+    // texture coordinates from (0,0) to (console_width,console_height), moved by the camera offset
+    vTextureCoord = uTermOffset + aTextureCoord * uTermSize;
 }
@@ -6,3 +6,6 @@ pub use buffer::*;
 
 mod console;
 pub use console::*;
+
+mod sprite;
+pub use sprite::*;
@@ -1,5 +1,5 @@
-use super::Buffer;
-use crate::{rgba::RGBA, Font};
+use super::{Buffer, MAX_FONTS};
+use crate::{rgba::RGBA, Font, MAX_ANIMATIONS};
 // use image::{ImageBuffer, Rgba};
 use std::collections::HashMap;
 use std::mem::size_of;
@@ -15,10 +15,12 @@ use uni_gl::{
 pub const DORYEN_VS: &str = include_str!("doryen_vs.glsl");
 pub const DORYEN_FS: &str = include_str!("doryen_fs.glsl");
 
-const FONT_TEXTURE: u32 = 0;
 const GLYPH_TEXTURE: u32 = 1;
 const FG_TEXTURE: u32 = 2;
 const BG_TEXTURE: u32 = 3;
+const ATTR_TEXTURE: u32 = 4;
+// the texture for each font slot
+const FONT_TEXTURES: [u32; MAX_FONTS as usize] = [0, 5, 6, 7];
 
 #[derive(Debug)]
 pub struct PrimitiveData {
@@ -43,13 +45,18 @@ impl PrimitiveData {
 
 #[derive(PartialEq, Eq, Hash, Copy, Clone)]
 pub enum DoryenUniforms {
-    Font,
+    Font(u8),
     Ascii,
     Foreground,
     Background,
-    FontCharsPerLine,
-    FontCoef,
+    Attrs,
+    FontCharsPerLine(u8),
+    FontCoef(u8),
+    FontFullColor(u8),
+    // font slot, animation index
+    FontAnim(u8, u8),
     TermSize,
+    TermOffset,
     TermCoef,
     ZPos,
 }
@@ -65,6 +72,9 @@ pub struct Program {
     pub(crate) ascii: WebGLTexture,
     pub(crate) foreground: WebGLTexture,
     pub(crate) background: WebGLTexture,
+    pub(crate) attrs: WebGLTexture,
+    // the cell attributes (font slot, transform flags) packed for the attrs texture
+    attr_data: Vec<u8>,
     pub(crate) uniform_locations: HashMap<DoryenUniforms, Option<WebGLUniformLocation>>,
     pub(crate) data: PrimitiveData,
 }
@@ -106,7 +116,7 @@ fn compile_shader_wasm_native(
     }
 }
 
-fn create_program(
+pub(crate) fn create_program(
     gl: &WebGLRenderingContext,
     vertex_source: &str,
     fragment_source: &str,
@@ -135,14 +145,13 @@ impl Program {
         let vertex_uv_buffer = vertex_uv_location.and(Some(gl.create_buffer()));
         let mut uniform_locations = HashMap::new();
         for uniform in [
-            (DoryenUniforms::Font, "uFont"),
             (DoryenUniforms::Ascii, "uAscii"),
             (DoryenUniforms::Background, "uBack"),
             (DoryenUniforms::Foreground, "uFront"),
-            (DoryenUniforms::FontCharsPerLine, "uFontCharsPerLine"),
-            (DoryenUniforms::FontCoef, "uFontCoef"),
+            (DoryenUniforms::Attrs, "uAttrs"),
             (DoryenUniforms::TermCoef, "uTermCoef"),
             (DoryenUniforms::TermSize, "uTermSize"),
+            (DoryenUniforms::TermOffset, "uTermOffset"),
             (DoryenUniforms::ZPos, "uZPos"), // If using ZPos
         ]
         .iter()
@@ -152,6 +161,27 @@ impl Program {
                 gl.get_uniform_location(&shader_program, uniform.1),
             );
         }
+        for slot in 0..MAX_FONTS {
+            for (uniform, name) in [
+                (DoryenUniforms::Font(slot), "uFont"),
+                (DoryenUniforms::FontCharsPerLine(slot), "uFontCharsPerLine"),
+                (DoryenUniforms::FontCoef(slot), "uFontCoef"),
+                (DoryenUniforms::FontFullColor(slot), "uFontFullColor"),
+            ] {
+                let name = format!("{}[{}]", name, slot);
+                uniform_locations.insert(uniform, gl.get_uniform_location(&shader_program, &name));
+            }
+            for index in 0..MAX_ANIMATIONS as u8 {
+                let name = format!(
+                    "uFontAnims[{}]",
+                    slot as usize * MAX_ANIMATIONS + index as usize
+                );
+                uniform_locations.insert(
+                    DoryenUniforms::FontAnim(slot, index),
+                    gl.get_uniform_location(&shader_program, &name),
+                );
+            }
+        }
 
         Program {
             program: shader_program,
@@ -164,6 +194,8 @@ impl Program {
             ascii: gl.create_texture(),
             foreground: gl.create_texture(),
             background: gl.create_texture(),
+            attrs: gl.create_texture(),
+            attr_data: Vec::new(),
             uniform_locations,
             data,
         }
@@ -215,21 +247,29 @@ impl Program {
         }
     }
 
-    pub(crate) fn use_font(&mut self, gl: &WebGLRenderingContext, font: &Font) {
+    /// Binds the font to the slot (0 = the console's font, see [`super::Buffer::font`])
+    ///
+    /// The time (in seconds) sets the frames of the font's animated glyphs.
+    pub(crate) fn use_font(
+        &mut self,
+        gl: &WebGLRenderingContext,
+        slot: u8,
+        font: &Font,
+        time: f64,
+    ) {
         gl.use_program(&self.program);
-        gl.active_texture(FONT_TEXTURE);
+        gl.active_texture(FONT_TEXTURES[slot as usize]);
         gl.bind_texture(&font.texture);
 
         if let Some(&Some(ref location)) = self
             .uniform_locations
-            .get(&DoryenUniforms::FontCharsPerLine)
+            .get(&DoryenUniforms::FontCharsPerLine(slot))
         {
-            gl.uniform_1f(
-                location,
-                (font.img_width() as f32) / (font.char_width() as f32),
-            );
+            gl.uniform_1f(location, font.columns() as f32);
         }
-        if let Some(&Some(ref location)) = self.uniform_locations.get(&DoryenUniforms::FontCoef) {
+        if let Some(&Some(ref location)) =
+            self.uniform_locations.get(&DoryenUniforms::FontCoef(slot))
+        {
             gl.uniform_2f(
                 location,
                 (
@@ -239,13 +279,55 @@ impl Program {
             );
         }
 
-        if let Some(&Some(ref sampler_location)) = self.uniform_locations.get(&DoryenUniforms::Font)
+        if let Some(Some(location)) = self
+            .uniform_locations
+            .get(&DoryenUniforms::FontFullColor(slot))
+        {
+            gl.uniform_1f(location, if font.full_color() { 1.0 } else { 0.0 });
+        }
+
+        font.with_animations(|animations| {
+            for index in 0..MAX_ANIMATIONS {
+                if let Some(Some(location)) = self
+                    .uniform_locations
+                    .get(&DoryenUniforms::FontAnim(slot, index as u8))
+                {
+                    let value = match animations.get(index) {
+                        None => (0.0, 0.0, 0.0, 0.0),
+                        Some(anim) => (
+                            anim.first as f32,
+                            anim.frames as f32,
+                            anim.cycle(time),
+                            if anim.lockstep { 1.0 } else { 0.0 },
+                        ),
+                    };
+                    gl.uniform_4f(location, value);
+                }
+            }
+        });
+
+        if let Some(&Some(ref sampler_location)) =
+            self.uniform_locations.get(&DoryenUniforms::Font(slot))
         {
-            gl.uniform_1i(sampler_location, FONT_TEXTURE as i32);
+            gl.uniform_1i(sampler_location, FONT_TEXTURES[slot as usize] as i32);
         }
     }
 
     pub fn render_buffer(&mut self, gl: &WebGLRenderingContext, buffer: &Buffer) {
+        let size = (buffer.width() as f32, buffer.height() as f32);
+        self.render_buffer_view(gl, buffer, (0.0, 0.0), size);
+    }
+
+    /// Renders the cells of the buffer from the offset (which can be fractional) to the offset + size into the extents
+    ///
+    /// The cells past the edges of the extents are clipped, see [`super::Console::set_camera_offset`].
+    pub fn render_buffer_view(
+        &mut self,
+        gl: &WebGLRenderingContext,
+        buffer: &Buffer,
+        offset: (f32, f32),
+        size: (f32, f32),
+    ) {
         gl.use_program(&self.program);
         self.set_uniforms(gl, buffer);
 
@@ -262,11 +344,12 @@ impl Program {
         }
 
         let (pot_width, pot_height) = buffer.pot_size();
-        let con_width = buffer.width();
-        let con_height = buffer.height();
 
         if let Some(&Some(ref location)) = self.uniform_locations.get(&DoryenUniforms::TermSize) {
-            gl.uniform_2f(location, (con_width as f32, con_height as f32));
+            gl.uniform_2f(location, size);
+        }
+        if let Some(Some(location)) = self.uniform_locations.get(&DoryenUniforms::TermOffset) {
+            gl.uniform_2f(location, offset);
         }
         if let Some(&Some(ref location)) = self.uniform_locations.get(&DoryenUniforms::TermCoef) {
             gl.uniform_2f(
@@ -342,10 +425,23 @@ impl Program {
             pot_width,
             pot_height,
         );
+        let mut attr_data = std::mem::take(&mut self.attr_data);
+        pack_attrs(buffer, &mut attr_data);
+        let attr_tex = WebGLTexture(self.attrs.0);
+        self.update_uniform_texture(
+            gl,
+            DoryenUniforms::Attrs,
+            ATTR_TEXTURE,
+            &attr_tex,
+            &attr_data,
+            pot_width,
+            pot_height,
+        );
+        self.attr_data = attr_data;
     }
 }
 
-fn set_buffer_data(
+pub(crate) fn set_buffer_data(
     gl: &WebGLRenderingContext,
     buffer: &WebGLBuffer,
     data: &Vec<f32>,
@@ -369,6 +465,14 @@ fn set_buffer_data(
     );
 }
 
+// r = font slot, g = transform flags
+fn pack_attrs(buffer: &Buffer, data: &mut Vec<u8>) {
+    data.clear();
+    for (font, transform) in buffer.fonts().iter().zip(buffer.transforms()) {
+        data.extend([*font, *transform, 0, 0]);
+    }
+}
+
 fn u32_to_u8(v: &[u32]) -> &[u8] {
     unsafe { slice::from_raw_parts(v.as_ptr() as *const u8, v.len() * size_of::<u32>()) }
 }
@@ -0,0 +1,337 @@
+use super::program::{create_program, set_buffer_data};
+use super::{Glyph, GLYPH_INDEX_MASK};
+use crate::{Font, RGBA};
+use uni_gl::{
+    AttributeSize, Primitives, WebGLBuffer, WebGLProgram, WebGLRenderingContext,
+    WebGLUniformLocation, WebGLVertexArray,
+};
+
+// shaders
+pub const SPRITE_VS: &str = include_str!("sprite_vs.glsl");
+pub const SPRITE_FS: &str = include_str!("sprite_fs.glsl");
+
+/// Where a sprite is on its console
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SpritePos {
+    /// The cell (x,y), which can be fractional - (2.5, 1.0) is half way between cells (2,1) and (3,1)
+    Cell(f32, f32),
+    /// The pixel (x,y) in the cells of the console's font - (12.0, 8.0) is cell (1.5, 1.0) with an 8x8 font
+    Pixel(f32, f32),
+}
+
+/// A glyph drawn over the cells of a [`super::Console`] at any position, see [`super::Console::add_sprite`]
+///
+/// Sprites are drawn after the cells, with the console's font, in z order (lowest first).
+/// The sprite covers one cell (times the scale) and turns around its center.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sprite {
+    /// The glyph to draw
+    pub glyph: Glyph,
+    /// The top left of the sprite (before scaling and rotating)
+    pub pos: SpritePos,
+    /// The color of the glyph
+    pub fg: RGBA,
+    /// The color behind the glyph (None = transparent)
+    pub bg: Option<RGBA>,
+    /// The size of the sprite in cells
+    pub scale: f32,
+    /// The clockwise rotation, in radians
+    pub rotation: f32,
+    /// The opacity, from 0 (invisible) to 1
+    pub alpha: f32,
+    /// The draw order, higher values are drawn on top
+    pub z: i32,
+}
+
+impl Sprite {
+    /// Constructs a sprite of the glyph at the cell (x,y), which can be fractional
+    pub fn new(glyph: Glyph, x: f32, y: f32) -> Self {
+        Sprite {
+            glyph,
+            pos: SpritePos::Cell(x, y),
+            fg: RGBA::rgb(255, 255, 255),
+            bg: None,
+            scale: 1.0,
+            rotation: 0.0,
+            alpha: 1.0,
+            z: 0,
+        }
+    }
+
+    /// Moves the sprite to the pixel (x,y) in the cells of the console's font
+    pub fn pixel_pos(mut self, x: f32, y: f32) -> Self {
+        self.pos = SpritePos::Pixel(x, y);
+        self
+    }
+
+    /// Sets the fg (default=WHITE)
+    pub fn fg(mut self, fg: RGBA) -> Self {
+        self.fg = fg;
+        self
+    }
+
+    /// Sets the bg (default=None)
+    pub fn bg(mut self, bg: RGBA) -> Self {
+        self.bg = Some(bg);
+        self
+    }
+
+    /// Sets the size in cells (default=1.0)
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the clockwise rotation in radians (default=0.0)
+    pub fn rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets the opacity (default=1.0)
+    pub fn alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Sets the draw order (default=0)
+    pub fn z(mut self, z: i32) -> Self {
+        self.z = z;
+        self
+    }
+
+    /// Moves the sprite to the cell (x,y), which can be fractional
+    pub fn move_to(&mut self, x: f32, y: f32) {
+        self.pos = SpritePos::Cell(x, y);
+    }
+
+    /// The position of the top left of the sprite in cells, using the font's cell size for pixel positions
+    pub fn cell_pos(&self, char_size: (u32, u32)) -> (f32, f32) {
+        match self.pos {
+            SpritePos::Cell(x, y) => (x, y),
+            SpritePos::Pixel(x, y) => {
+                (x / char_size.0.max(1) as f32, y / char_size.1.max(1) as f32)
+            }
+        }
+    }
+}
+
+// where the console is drawn - to place the sprites
+pub(crate) struct SpriteLayout {
+    // the console extents (left, top, right, bottom) as fractions of the screen
+    pub extents: (f32, f32, f32, f32),
+    // the camera offset in cells
+    pub offset: (f32, f32),
+    // the number of cells shown
+    pub cells: (u32, u32),
+    // the font's cell size in pixels
+    pub char_size: (u32, u32),
+    // the screen size in pixels
+    pub screen_size: (u32, u32),
+}
+
+impl SpriteLayout {
+    // the corners of the sprite in gl coordinates - top left, top right, bottom right, bottom left
+    pub(crate) fn corners(&self, sprite: &Sprite) -> [(f32, f32); 4] {
+        let (screen_width, screen_height) = (self.screen_size.0 as f32, self.screen_size.1 as f32);
+        let (left, top, right, bottom) = self.extents;
+        let cell_width = (right - left) * screen_width / self.cells.0.max(1) as f32;
+        let cell_height = (bottom - top) * screen_height / self.cells.1.max(1) as f32;
+
+        let (x, y) = sprite.cell_pos(self.char_size);
+        let (x, y) = (x - self.offset.0, y - self.offset.1);
+        let center = (
+            left * screen_width + (x + 0.5) * cell_width,
+            top * screen_height + (y + 0.5) * cell_height,
+        );
+        let half = (
+            cell_width * sprite.scale / 2.0,
+            cell_height * sprite.scale / 2.0,
+        );
+        // y is down, so this turns clockwise
+        let (sin, cos) = sprite.rotation.sin_cos();
+        [
+            (-half.0, -half.1),
+            (half.0, -half.1),
+            (half.0, half.1),
+            (-half.0, half.1),
+        ]
+        .map(|(dx, dy)| {
+            let px = center.0 + dx * cos - dy * sin;
+            let py = center.1 + dx * sin + dy * cos;
+            (
+                px / screen_width * 2.0 - 1.0,
+                1.0 - py / screen_height * 2.0,
+            )
+        })
+    }
+}
+
+// the texture coordinates of the glyph - left, top, right, bottom
+fn glyph_coords(font: &Font, glyph: Glyph) -> (f32, f32, f32, f32) {
+    let index = glyph & GLYPH_INDEX_MASK;
+    let columns = font.columns().max(1);
+    let width = font.char_width() as f32 / font.img_width() as f32;
+    let height = font.char_height() as f32 / font.img_height() as f32;
+    let left = (index % columns) as f32 * width;
+    let top = (index / columns) as f32 * height;
+    (left, top, left + width, top + height)
+}
+
+/// Draws the sprites of the consoles
+pub struct SpriteProgram {
+    program: WebGLProgram,
+    vao: WebGLVertexArray,
+    // buffer and attribute location for the vertex positions, texture coords, fg and bg
+    attributes: Vec<(WebGLBuffer, Option<u32>)>,
+    font_location: Option<WebGLUniformLocation>,
+    full_color_location: Option<WebGLUniformLocation>,
+    clip_location: Option<WebGLUniformLocation>,
+    // the vertex data
+    pos_data: Vec<f32>,
+    tex_data: Vec<f32>,
+    fg_data: Vec<f32>,
+    bg_data: Vec<f32>,
+}
+
+impl SpriteProgram {
+    pub fn new(gl: &WebGLRenderingContext) -> SpriteProgram {
+        let program = create_program(gl, SPRITE_VS, SPRITE_FS);
+        let vao = gl.create_vertex_array();
+        let attributes = ["aVertexPosition", "aTextureCoord", "aFore", "aBack"]
+            .iter()
+            .map(|name| (gl.create_buffer(), gl.get_attrib_location(&program, name)))
+            .collect();
+        SpriteProgram {
+            font_location: gl.get_uniform_location(&program, "uFont"),
+            full_color_location: gl.get_uniform_location(&program, "uFontFullColor"),
+            clip_location: gl.get_uniform_location(&program, "uClip"),
+            program,
+            vao,
+            attributes,
+            pos_data: Vec::new(),
+            tex_data: Vec::new(),
+            fg_data: Vec::new(),
+            bg_data: Vec::new(),
+        }
+    }
+
+    /// Draws the sprites (in z order) with the font, clipped to the console
+    pub(crate) fn render(
+        &mut self,
+        gl: &WebGLRenderingContext,
+        font: &Font,
+        layout: &SpriteLayout,
+        sprites: &[Sprite],
+    ) {
+        let mut sorted: Vec<&Sprite> = sprites.iter().filter(|s| s.alpha > 0.0).collect();
+        if sorted.is_empty() {
+            return;
+        }
+        sorted.sort_by_key(|s| s.z);
+
+        self.pos_data.clear();
+        self.tex_data.clear();
+        self.fg_data.clear();
+        self.bg_data.clear();
+        for sprite in sorted {
+            let corners = layout.corners(sprite);
+            let (left, top, right, bottom) = glyph_coords(font, sprite.glyph);
+            let coords = [(left, top), (right, top), (right, bottom), (left, bottom)];
+            let alpha = sprite.alpha.min(1.0);
+            let fg = sprite.fg.to_f32();
+            let bg = sprite
+                .bg
+                .map(|bg| bg.to_f32())
+                .unwrap_or((0.0, 0.0, 0.0, 0.0));
+            // two triangles
+            for i in [0, 1, 2, 0, 2, 3] {
+                self.pos_data.extend([corners[i].0, corners[i].1]);
+                self.tex_data.extend([coords[i].0, coords[i].1]);
+                self.fg_data.extend([fg.0, fg.1, fg.2, fg.3 * alpha]);
+                self.bg_data.extend([bg.0, bg.1, bg.2, bg.3 * alpha]);
+            }
+        }
+
+        gl.use_program(&self.program);
+        gl.bind_vertex_array(&self.vao);
+        let data = [&self.pos_data, &self.tex_data, &self.fg_data, &self.bg_data];
+        let sizes = [
+            AttributeSize::Two,
+            AttributeSize::Two,
+            AttributeSize::Four,
+            AttributeSize::Four,
+        ];
+        for (((buffer, location), data), size) in self.attributes.iter().zip(data).zip(sizes) {
+            if let Some(location) = location {
+                set_buffer_data(gl, buffer, data, *location, size);
+            }
+        }
+
+        gl.active_texture(0);
+        gl.bind_texture(&font.texture);
+        if let Some(location) = &self.font_location {
+            gl.uniform_1i(location, 0);
+        }
+        if let Some(location) = &self.full_color_location {
+            gl.uniform_1f(location, if font.full_color() { 1.0 } else { 0.0 });
+        }
+        if let Some(location) = &self.clip_location {
+            let (left, top, right, bottom) = layout.extents;
+            gl.uniform_4f(
+                location,
+                (
+                    left * 2.0 - 1.0,
+                    1.0 - bottom * 2.0,
+                    right * 2.0 - 1.0,
+                    1.0 - top * 2.0,
+                ),
+            );
+        }
+
+        gl.draw_arrays(Primitives::Triangles, self.pos_data.len() / 2);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 0.0001 && (a.1 - b.1).abs() < 0.0001
+    }
+
+    #[test]
+    fn corners() {
+        // a 10x10 console of 8x8 cells on the right half of a 160x80 screen
+        let layout = SpriteLayout {
+            extents: (0.5, 0.0, 1.0, 1.0),
+            offset: (0.0, 0.0),
+            cells: (10, 10),
+            char_size: (8, 8),
+            screen_size: (160, 80),
+        };
+
+        let sprite = Sprite::new(1, 0.0, 0.0);
+        let corners = layout.corners(&sprite);
+        assert!(close(corners[0], (0.0, 1.0)));
+        assert!(close(corners[2], (0.1, 0.8)));
+
+        // the same place in pixels
+        let sprite = Sprite::new(1, 0.0, 0.0).pixel_pos(12.0, 4.0);
+        assert_eq!(sprite.cell_pos((8, 8)), (1.5, 0.5));
+        let corners = layout.corners(&sprite);
+        assert!(close(corners[0], (0.15, 0.9)));
+
+        // twice the size, around the same center
+        let corners = layout.corners(&Sprite::new(1, 1.0, 1.0).scale(2.0));
+        assert!(close(corners[0], (0.05, 0.9)));
+        assert!(close(corners[2], (0.25, 0.5)));
+
+        // a quarter turn clockwise moves the top left corner to the top right
+        let sprite = Sprite::new(1, 1.0, 1.0).rotation(std::f32::consts::FRAC_PI_2);
+        let corners = layout.corners(&sprite);
+        assert!(close(corners[0], (0.2, 0.8)));
+        assert!(close(corners[1], (0.2, 0.6)));
+    }
+}
@@ -0,0 +1,22 @@
+precision mediump float;
+uniform sampler2D uFont; // the console's font
+// 1.0 = the glyphs keep their own colors, 0.0 = the glyphs are tinted with the foreground color
+uniform float uFontFullColor;
+// the console - left, bottom, right, top (from -1 to 1)
+uniform vec4 uClip;
+
+in vec2 vTextureCoord;
+in vec4 vFore; // the sprite color (alpha includes the sprite alpha)
+in vec4 vBack; // the color behind the glyph (alpha is 0 for none)
+in vec2 vPos;
+out vec4 FragColor;
+
+void main() {
+    // sprites are only drawn over their console
+    if (vPos.x < uClip.x || vPos.x > uClip.z || vPos.y < uClip.y || vPos.y > uClip.w) {
+        discard;
+    }
+    vec4 font_color = texture(uFont, vTextureCoord);
+    vec4 tint = mix(vFore, vec4(1.0, 1.0, 1.0, vFore.a), uFontFullColor);
+    FragColor = font_color.a * tint * vec4(font_color.rgb, 1.0) + (1.0 - font_color.a) * vBack;
+}
@@ -0,0 +1,17 @@
+in vec2 aVertexPosition;
+in vec2 aTextureCoord;
+in vec4 aFore;
+in vec4 aBack;
+out vec2 vTextureCoord;
+out vec4 vFore;
+out vec4 vBack;
+out vec2 vPos;
+void main(void) {
+    // vertex position from (-1,-1) to (1,1)
+    gl_Position = vec4(aVertexPosition.xy, 0.0, 1.0);
+    vPos = aVertexPosition;
+    // texture coordinates of the glyph in the font
+    vTextureCoord = aTextureCoord;
+    vFore = aFore;
+    vBack = aBack;
+}
@@ -1,74 +1,121 @@
+use super::{byte_at_col, display_width, ends_wide, last_space_before};
 use std::cmp::min;
 
-/// A span of the input text that is in a single color
+/// The colors and effect in use for a span - (fg, bg, effect), None = use the printer default
+pub type SpanColors<'a> = (Option<&'a str>, Option<&'a str>, Option<&'a str>);
+
+/// A span of the input text that is in a single set of colors
+///
+/// A span can also be a single inline glyph (from `#{219}` or `#{name}` in the markup)
 #[derive(Debug, Clone)]
 pub struct ColoredSpan<'a> {
-    color: Option<&'a str>,
+    fg: Option<&'a str>,
+    bg: Option<&'a str>,
+    effect: Option<&'a str>,
     txt: &'a str,
+    glyph: bool,
 }
 
 impl<'a> ColoredSpan<'a> {
-    /// Constructs a new span
-    fn new(color: Option<&'a str>, txt: &'a str) -> Self {
-        ColoredSpan { color, txt }
+    /// Constructs a new span with the colors and effect
+    fn styled(style: SpanColors<'a>, txt: &'a str) -> Self {
+        let (fg, bg, effect) = style;
+        ColoredSpan {
+            fg,
+            bg,
+            effect,
+            txt,
+            glyph: false,
+        }
+    }
+
+    /// Constructs a span that is a single glyph, given by code or name
+    fn new_glyph(style: SpanColors<'a>, code: &'a str) -> Self {
+        ColoredSpan {
+            glyph: true,
+            ..ColoredSpan::styled(style, code)
+        }
+    }
+
+    /// Returns a span of the given text using the same colors and effect
+    fn with_txt(&self, txt: &'a str) -> Self {
+        ColoredSpan::styled((self.fg, self.bg, self.effect), txt)
+    }
+
+    /// The fg color of the span
+    pub fn fg(&self) -> Option<&'a str> {
+        self.fg
+    }
+
+    /// The bg color of the span
+    pub fn bg(&self) -> Option<&'a str> {
+        self.bg
     }
 
+    /// The fg color of the span
+    #[deprecated(note = "use fg (or bg for the background color)")]
     pub fn color(&self) -> Option<&'a str> {
-        self.color
+        self.fg()
+    }
+
+    /// The name of the effect of the span (e.g. "wave" for `#[~wave]`), see [`super::TextEffect`]
+    pub fn effect(&self) -> Option<&'a str> {
+        self.effect
+    }
+
+    /// The code or name of the glyph if this span is an inline glyph (e.g. "219" for `#{219}`)
+    pub fn glyph(&self) -> Option<&'a str> {
+        match self.glyph {
+            true => Some(self.txt),
+            false => None,
+        }
     }
 
+    /// The text of the span (the glyph code or name for glyph spans)
     pub fn as_str(&self) -> &'a str {
         self.txt
     }
 
-    /// Length of the span in chars
+    /// Length of the span in cells (wide chars count as 2)
     pub fn char_len(&self) -> usize {
-        self.txt.chars().count()
+        match self.glyph {
+            true => 1,
+            false => display_width(self.txt),
+        }
     }
 
-    /// The position of the last space before the given index
+    /// The position (in cells) of the last space before the given index
     fn last_break_before(&self, char_idx: usize) -> Option<usize> {
-        if char_idx == 0 {
+        if char_idx == 0 || self.glyph {
             return None;
         }
-        match self.txt.char_indices().nth(char_idx) {
-            // we only get this if are past the end of the slice
-            None => match self.txt.rmatch_indices(' ').next() {
-                None => None,
-                Some((idx, _)) => Some(idx),
-            },
-            Some((idx, _)) => match self.txt[..idx].rmatch_indices(' ').next() {
-                None => None,
-                Some((idx, _)) => Some(idx),
-            },
-        }
+        last_space_before(self.txt, char_idx)
     }
 
     /// Splits the span into 2 with the index being the first char on the right side
     fn split_at_idx(&self, char_idx: usize) -> (Self, Self) {
-        let idx = self
-            .txt
-            .char_indices()
-            .nth(char_idx)
-            .map(|(i, _)| i)
-            .unwrap();
+        if self.glyph {
+            return match char_idx {
+                0 => (self.with_txt(""), self.clone()),
+                _ => (self.clone(), self.with_txt("")),
+            };
+        }
+        let idx = byte_at_col(self.txt, char_idx);
         (
-            ColoredSpan::new(self.color, &self.txt[..idx]),
-            ColoredSpan::new(self.color, &self.txt[idx..]),
+            self.with_txt(&self.txt[..idx]),
+            self.with_txt(&self.txt[idx..]),
         )
     }
 
     /// Splits the span into 2 with the index being omitted
     fn split_omitting(&self, omit_idx: usize) -> (Self, Self) {
-        let idx = self
-            .txt
-            .char_indices()
-            .nth(omit_idx)
-            .map(|(i, _)| i)
-            .unwrap();
+        if self.glyph {
+            return (self.with_txt(""), self.with_txt(""));
+        }
+        let idx = byte_at_col(self.txt, omit_idx);
         (
-            ColoredSpan::new(self.color, &self.txt[..idx]),
-            ColoredSpan::new(self.color, &self.txt[idx + 1..]),
+            self.with_txt(&self.txt[..idx]),
+            self.with_txt(&self.txt[idx + 1..]),
         )
     }
 }
@@ -85,9 +132,18 @@ impl<'a> ColoredSpan<'a> {
 
 impl<'a> ToString for ColoredSpan<'a> {
     fn to_string(&self) -> String {
-        match self.color {
-            None => format!("#[]{}", self.txt),
-            Some(c) => format!("#[{}]{}", c, self.txt),
+        let effect = match self.effect {
+            None => String::new(),
+            Some(name) => format!("~{}", name),
+        };
+        let colors = match (self.fg, self.bg) {
+            (None, None) => format!("#[{}]", effect),
+            (Some(fg), None) => format!("#[{}{}]", fg, effect),
+            (fg, Some(bg)) => format!("#[{}:{}{}]", fg.unwrap_or(""), bg, effect),
+        };
+        match self.glyph {
+            true => format!("{}#{{{}}}", colors, self.txt),
+            false => format!("{}{}", colors, super::escape_markup(self.txt)),
         }
     }
 }
@@ -152,10 +208,10 @@ impl<'a> ColoredLine<'a> {
     fn first_word(&self) -> Self {
         let mut out = ColoredLine::new();
         for span in self.spans.iter() {
-            match span.txt.find(" ") {
+            match span.txt.find(' ').filter(|_| !span.glyph) {
                 None => out.push(span.clone()),
                 Some(idx) => {
-                    out.push(ColoredSpan::new(span.color, &span.txt[..idx]));
+                    out.push(span.with_txt(&span.txt[..idx]));
                     break;
                 }
             }
@@ -190,9 +246,17 @@ impl<'a> ColoredLine<'a> {
         //     }
         // }
 
+        // wide (CJK) text can break anywhere, so it does not need a hyphen (and gets the extra cell)
+        let (left, right) = self.split_at_char(split_idx + 1);
+        if let Some(last_left) = left.spans.last() {
+            if !last_left.glyph && ends_wide(last_left.txt) {
+                return (left, right);
+            }
+        }
+
         let (mut left, right) = self.split_at_char(split_idx);
         if let Some(last_left) = left.spans.last() {
-            left.push(ColoredSpan::new(last_left.color, "-"));
+            left.push(last_left.with_txt("-"));
         };
 
         (left, right)
@@ -277,13 +341,53 @@ impl<'a> ToString for ColoredLine<'a> {
     }
 }
 
+/// The kinds of errors in colored markup
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MarkupErrKind {
+    /// Found a `#[` without a closing `]`
+    UnclosedColor,
+    /// Found a `#{` without a closing `}`
+    UnclosedGlyph,
+    /// Found a `#{}` without a glyph code or name
+    EmptyGlyph,
+    /// Found a `#[]` with no colors to pop
+    ExtraPop,
+}
+
+/// An error in colored markup, found by [`try_parse_colored_line`] or [`try_parse_colored_lines`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MarkupErr {
+    /// What went wrong
+    pub kind: MarkupErrKind,
+    /// The index of the line with the error (always 0 for [`try_parse_colored_line`])
+    pub line: usize,
+    /// The byte offset in the line of the `#` that starts the bad markup
+    pub pos: usize,
+}
+
+impl std::fmt::Display for MarkupErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self.kind {
+            MarkupErrKind::UnclosedColor => "'#[' without closing ']'",
+            MarkupErrKind::UnclosedGlyph => "'#{' without closing '}'",
+            MarkupErrKind::EmptyGlyph => "'#{}' without glyph",
+            MarkupErrKind::ExtraPop => "'#[]' without colors to pop",
+        };
+        write!(f, "{} at line {}, pos {}", msg, self.line, self.pos)
+    }
+}
+
+impl std::error::Error for MarkupErr {}
+
 /// Converts text into colored lines
+///
+/// Bad markup is kept as plain text, see [`parse_colored_line_ex`].
 pub fn parse_colored_lines<'a>(txt: &'a str) -> Vec<ColoredLine<'a>> {
-    let mut colors: Vec<Option<&str>> = Vec::new();
+    let mut colors: Vec<SpanColors<'a>> = Vec::new();
     let mut out: Vec<ColoredLine<'a>> = Vec::new();
 
     for line in txt.split('\n') {
-        let colored_line = parse_colored_line(line, &mut colors);
+        let colored_line = parse_colored_line_ex(line, &mut colors);
         out.push(colored_line);
     }
 
@@ -292,38 +396,153 @@ pub fn parse_colored_lines<'a>(txt: &'a str) -> Vec<ColoredLine<'a>> {
     out
 }
 
-/// Parses a single line
+/// Converts text into colored lines, returning the first markup error found
+pub fn try_parse_colored_lines<'a>(txt: &'a str) -> Result<Vec<ColoredLine<'a>>, MarkupErr> {
+    let mut colors: Vec<SpanColors<'a>> = Vec::new();
+    let mut out: Vec<ColoredLine<'a>> = Vec::new();
+
+    for (i, line) in txt.split('\n').enumerate() {
+        match parse_line(line, &mut colors, true) {
+            Err(e) => return Err(MarkupErr { line: i, ..e }),
+            Ok(colored_line) => out.push(colored_line),
+        }
+    }
+    Ok(out)
+}
+
+/// Parses a single line, keeping a stack of fg colors
+///
+/// See [`parse_colored_line_ex`] for the markup.  The colors carry over to the next line, but only the fg colors are kept.
 pub fn parse_colored_line<'a>(line: &'a str, colors: &mut Vec<Option<&'a str>>) -> ColoredLine<'a> {
-    let mut colored_line = ColoredLine::new();
-    let default_color: Option<&str> = None;
+    let mut styles: Vec<SpanColors<'a>> = colors.iter().map(|fg| (*fg, None, None)).collect();
+    let colored_line = parse_colored_line_ex(line, &mut styles);
+    *colors = styles.iter().map(|style| style.0).collect();
+    colored_line
+}
+
+/// Parses a single line, keeping a stack of the colors and effects
+///
+/// The markup is:
+/// - `#[fg]` - push a new fg color, keeping the current bg
+/// - `#[fg:bg]` - push new fg and bg colors (`#[:bg]` changes only the bg)
+/// - `#[]` - pop the last colors pushed
+/// - `#[~name]` - push an effect, keeping the current colors (`#[fg:bg~name]` sets both, `#[~]` ends the effect)
+/// - `#[/]` - reset all colors and effects back to the defaults
+/// - `#{219}`, `#{0xDB}` or `#{name}` - an inline glyph by code or name
+/// - `#[[` and `#{{` - the literal text `#[` and `#{`
+///
+/// The color stack carries over to the next line.
+///
+/// This is lenient - markup that is not closed (or an empty `#{}`) is kept as plain text
+/// and extra `#[]` are ignored.  Use [`try_parse_colored_line`] to find these errors.
+pub fn parse_colored_line_ex<'a>(
+    line: &'a str,
+    colors: &mut Vec<SpanColors<'a>>,
+) -> ColoredLine<'a> {
+    match parse_line(line, colors, false) {
+        Ok(colored_line) => colored_line,
+        Err(_) => unreachable!(),
+    }
+}
+
+/// Parses a single line, returning an error for any bad markup
+///
+/// See [`parse_colored_line_ex`] for the markup.
+pub fn try_parse_colored_line<'a>(
+    line: &'a str,
+    colors: &mut Vec<SpanColors<'a>>,
+) -> Result<ColoredLine<'a>, MarkupErr> {
+    parse_line(line, colors, true)
+}
 
-    for (i, major_part) in line.split("#[").enumerate() {
-        if major_part.len() == 0 {
+fn parse_line<'a>(
+    line: &'a str,
+    colors: &mut Vec<SpanColors<'a>>,
+    strict: bool,
+) -> Result<ColoredLine<'a>, MarkupErr> {
+    let mut colored_line = ColoredLine::new();
+    let default_colors: SpanColors<'a> = (None, None, None);
+
+    let mut rest = line;
+    loop {
+        let style = *colors.last().unwrap_or(&default_colors);
+        let (fg, bg, effect) = style;
+        let idx = match (rest.find("#["), rest.find("#{")) {
+            (None, None) => break,
+            (Some(a), Some(b)) => min(a, b),
+            (Some(a), None) => a,
+            (None, Some(b)) => b,
+        };
+        if idx > 0 {
+            colored_line.push(ColoredSpan::styled(style, &rest[..idx]));
+        }
+        let pos = line.len() - rest.len() + idx;
+        let markup = &rest[idx..idx + 2];
+        let open = &markup[1..];
+        let close = if open == "[" { "]" } else { "}" };
+        rest = &rest[idx + 2..];
+
+        // "#[[" or "#{{" is an escaped "#[" or "#{"
+        if rest.starts_with(open) {
+            colored_line.push(ColoredSpan::styled(style, markup));
+            rest = &rest[1..];
             continue;
-        } // skip empty parts
-        if i == 0 {
-            colored_line.push(ColoredSpan::new(default_color, major_part));
-        } else if major_part.starts_with("[") {
-            let c = colors.iter().last().unwrap_or(&default_color);
-            colored_line.push(ColoredSpan::new(c.clone(), "#["));
-            colored_line.push(ColoredSpan::new(c.clone(), &major_part[1..]));
-        } else {
-            match major_part.split_once("]") {
-                None => panic!("Parsing error! - {}", line),
-                Some((color, text)) => {
-                    if color.len() == 0 {
-                        colors.pop();
-                    } else {
-                        colors.push(Some(color));
-                    }
-                    let c = colors.iter().last().unwrap_or(&default_color);
-                    colored_line.push(ColoredSpan::new(c.clone(), text));
+        }
+
+        let err = |kind| MarkupErr { kind, line: 0, pos };
+        let (inner, after) = match rest.split_once(close) {
+            None if strict => {
+                return Err(err(match open {
+                    "[" => MarkupErrKind::UnclosedColor,
+                    _ => MarkupErrKind::UnclosedGlyph,
+                }))
+            }
+            None => {
+                colored_line.push(ColoredSpan::styled(style, markup));
+                continue;
+            }
+            Some(parts) => parts,
+        };
+
+        if open == "{" {
+            if inner.is_empty() {
+                if strict {
+                    return Err(err(MarkupErrKind::EmptyGlyph));
                 }
+                colored_line.push(ColoredSpan::styled(style, &line[pos..pos + 3]));
+            } else {
+                colored_line.push(ColoredSpan::new_glyph(style, inner));
+            }
+        } else if inner.is_empty() {
+            if colors.pop().is_none() && strict {
+                return Err(err(MarkupErrKind::ExtraPop));
             }
+        } else if inner == "/" {
+            colors.clear();
+        } else {
+            let (inner, new_effect) = match inner.split_once('~') {
+                None => (inner, effect),
+                Some((inner, name)) => (inner, Some(name).filter(|n| !n.is_empty())),
+            };
+            let (new_fg, new_bg) = match inner.split_once(':') {
+                None if inner.is_empty() => (fg, bg),
+                None => (Some(inner), bg),
+                Some((new_fg, new_bg)) => (
+                    if new_fg.is_empty() { fg } else { Some(new_fg) },
+                    if new_bg.is_empty() { bg } else { Some(new_bg) },
+                ),
+            };
+            colors.push((new_fg, new_bg, new_effect));
         }
+        rest = after;
     }
 
-    colored_line
+    if !rest.is_empty() {
+        let style = *colors.last().unwrap_or(&default_colors);
+        colored_line.push(ColoredSpan::styled(style, rest));
+    }
+
+    Ok(colored_line)
 }
 
 /// Parses the text and wraps it into lines with a max of the given width
@@ -331,59 +550,65 @@ pub fn wrap_colored<'a>(limit: usize, text: &'a str) -> Vec<ColoredLine<'a>> {
     // println!("--------------------------------------");
     // println!("WRAP - {}: '{}'", limit, text);
 
-    let mut output: Vec<ColoredLine<'a>> = Vec::new();
-
-    for mut current in parse_colored_lines(text) {
-        let mut i = 0;
-
-        while current.char_len() > limit {
-            i += 1;
-            if i > 10 {
-                break;
-            }
-
-            match current.last_break_before(limit + 1) {
-                None => {
-                    let first_word = current.first_word();
-                    let first_word_len = first_word.char_len();
+    parse_colored_lines(text)
+        .into_iter()
+        .flat_map(|line| wrap_colored_line(limit, line))
+        .collect()
+}
 
-                    let keep_len = min(limit.saturating_sub(1), first_word_len.saturating_sub(2));
-                    let (left, right) = current.hyphenate_at_char(keep_len);
+/// Wraps a single (already parsed) line into lines with a max of the given width
+pub(crate) fn wrap_colored_line(limit: usize, line: ColoredLine<'_>) -> Vec<ColoredLine<'_>> {
+    let mut output = Vec::new();
+    let mut current = line;
 
-                    // println!("too long - {} => {} + {}", first_word, left, right);
-                    // println!(": {}", left);
-                    output.push(left);
-                    current = right;
-                }
-                Some(break_index) => {
-                    let (mut left, mut right) = current.split_omitting(break_index);
-                    let left_len = left.char_len();
-                    let line_left = limit.saturating_sub(left_len).saturating_sub(1);
+    while current.char_len() > limit {
+        // stop if nothing fits (e.g. a wide glyph in a 1 cell limit)
+        let before = current.char_len();
 
-                    // println!(" - left={}, line_left={}, right={}", left, line_left, right);
-                    if line_left >= 4 {
-                        let next_word = right.first_word();
-                        let next_word_len = next_word.char_len();
+        match current.last_break_before(limit + 1) {
+            None => {
+                let first_word = current.first_word();
+                let first_word_len = first_word.char_len();
 
-                        // println!(" - : next_word={}, len={}", next_word, next_word_len);
+                let keep_len = min(limit.saturating_sub(1), first_word_len.saturating_sub(2));
+                let (left, right) = current.hyphenate_at_char(keep_len);
 
-                        if next_word_len >= 6 {
-                            let keep_len = min(line_left, next_word_len - 2);
-                            // println!(" - : hyphen! keep={}", keep_len);
-                            (left, right) = current.hyphenate_at_char(break_index + keep_len);
-                        }
+                // println!("too long - {} => {} + {}", first_word, left, right);
+                // println!(": {}", left);
+                output.push(left);
+                current = right;
+            }
+            Some(break_index) => {
+                let (mut left, mut right) = current.split_omitting(break_index);
+                let left_len = left.char_len();
+                let line_left = limit.saturating_sub(left_len).saturating_sub(1);
+
+                // println!(" - left={}, line_left={}, right={}", left, line_left, right);
+                if line_left >= 4 {
+                    let next_word = right.first_word();
+                    let next_word_len = next_word.char_len();
+
+                    // println!(" - : next_word={}, len={}", next_word, next_word_len);
+
+                    if next_word_len >= 6 {
+                        let keep_len = min(line_left, next_word_len - 2);
+                        // println!(" - : hyphen! keep={}", keep_len);
+                        (left, right) = current.hyphenate_at_char(break_index + keep_len);
                     }
-                    // println!(": {}", left);
-                    output.push(left);
-                    current = right;
                 }
+                // println!(": {}", left);
+                output.push(left);
+                current = right;
             }
         }
-
-        if current.char_len() > 0 {
-            output.push(current);
+        if current.char_len() >= before {
+            break;
         }
     }
+
+    if current.char_len() > 0 {
+        output.push(current);
+    }
     output
 }
 
@@ -395,13 +620,9 @@ pub fn wrap_colored_no_hyphen<'a>(limit: usize, text: &'a str) -> Vec<ColoredLin
     let mut output: Vec<ColoredLine<'a>> = Vec::new();
 
     for mut current in parse_colored_lines(text) {
-        let mut i = 0;
-
         while current.char_len() > limit {
-            i += 1;
-            if i > 10 {
-                break;
-            }
+            // stop if nothing fits (e.g. a wide glyph in a 1 cell limit)
+            let before = current.char_len();
 
             match current.last_break_before(limit + 1) {
                 None => {
@@ -439,6 +660,9 @@ pub fn wrap_colored_no_hyphen<'a>(limit: usize, text: &'a str) -> Vec<ColoredLin
                     current = right;
                 }
             }
+            if current.char_len() >= before {
+                break;
+            }
         }
 
         if current.char_len() > 0 {
@@ -456,7 +680,7 @@ mod test {
     #[test]
     fn span_last_break_before() {
         let text = "This is a span of text";
-        let span = ColoredSpan::new(Some("color"), text);
+        let span = ColoredSpan::styled((Some("color"), None, None), text);
 
         assert_eq!(span.last_break_before(0), None);
         assert_eq!(span.last_break_before(4), None);
@@ -465,7 +689,7 @@ mod test {
         assert_eq!(span.last_break_before(20), Some(17));
 
         let text = "This is a ";
-        let span = ColoredSpan::new(Some("color"), text);
+        let span = ColoredSpan::styled((Some("color"), None, None), text);
 
         assert_eq!(span.last_break_before(0), None);
         assert_eq!(span.last_break_before(0), None);
@@ -478,7 +702,7 @@ mod test {
     fn line_last_break_before() {
         let text = "This is a #[00F]span#[] of text";
         let mut colors = Vec::new();
-        let line = parse_colored_line(text, &mut colors);
+        let line = parse_colored_line_ex(text, &mut colors);
         // let mut buffer = Buffer::new(50, 50);
 
         assert_eq!(line.last_break_before(0), None);
@@ -491,49 +715,49 @@ mod test {
     #[test]
     fn span_split_at_space() {
         let text = "This is a span of text";
-        let span = ColoredSpan::new(Some("color"), text);
+        let span = ColoredSpan::styled((Some("color"), None, None), text);
 
         let (left, right) = span.split_omitting(9);
         assert_eq!(left.txt, "This is a");
         assert_eq!(right.txt, "span of text");
-        assert_eq!(left.color, Some("color"));
-        assert_eq!(right.color, Some("color"));
+        assert_eq!(left.fg, Some("color"));
+        assert_eq!(right.fg, Some("color"));
 
         let (left, right) = span.split_omitting(0);
         assert_eq!(left.txt, "");
         assert_eq!(right.txt, "his is a span of text");
-        assert_eq!(left.color, Some("color"));
-        assert_eq!(right.color, Some("color"));
+        assert_eq!(left.fg, Some("color"));
+        assert_eq!(right.fg, Some("color"));
 
         let (left, right) = span.split_omitting(span.char_len() - 1);
         assert_eq!(left.txt, "This is a span of tex");
         assert_eq!(right.txt, "");
-        assert_eq!(left.color, Some("color"));
-        assert_eq!(right.color, Some("color"));
+        assert_eq!(left.fg, Some("color"));
+        assert_eq!(right.fg, Some("color"));
     }
 
     #[test]
     fn span_split_at_char() {
         let text = "This is a span of text";
-        let span = ColoredSpan::new(Some("color"), text);
+        let span = ColoredSpan::styled((Some("color"), None, None), text);
 
         let (left, right) = span.split_at_idx(9);
         assert_eq!(left.txt, "This is a");
         assert_eq!(right.txt, " span of text");
-        assert_eq!(left.color, Some("color"));
-        assert_eq!(right.color, Some("color"));
+        assert_eq!(left.fg, Some("color"));
+        assert_eq!(right.fg, Some("color"));
 
         let (left, right) = span.split_at_idx(0);
         assert_eq!(left.txt, "");
         assert_eq!(right.txt, "This is a span of text");
-        assert_eq!(left.color, Some("color"));
-        assert_eq!(right.color, Some("color"));
+        assert_eq!(left.fg, Some("color"));
+        assert_eq!(right.fg, Some("color"));
 
         let (left, right) = span.split_at_idx(span.char_len() - 1);
         assert_eq!(left.txt, "This is a span of tex");
         assert_eq!(right.txt, "t");
-        assert_eq!(left.color, Some("color"));
-        assert_eq!(right.color, Some("color"));
+        assert_eq!(left.fg, Some("color"));
+        assert_eq!(right.fg, Some("color"));
     }
 
     fn line_string(line: &ColoredLine) -> String {
@@ -548,7 +772,7 @@ mod test {
     fn line_split_at_space() {
         let text = "This is a #[00F]span#[] of text";
         let mut colors = Vec::new();
-        let line = parse_colored_line(text, &mut colors);
+        let line = parse_colored_line_ex(text, &mut colors);
 
         let (left, right) = line.split_omitting(9);
         assert_eq!(line_string(&left), "This is a");
@@ -567,7 +791,7 @@ mod test {
     fn line_hyphenate_at_char() {
         let text = "This is a #[00F]span#[] of text";
         let mut colors = Vec::new();
-        let line = parse_colored_line(text, &mut colors);
+        let line = parse_colored_line_ex(text, &mut colors);
 
         let (left, right) = line.hyphenate_at_char(12);
         assert_eq!(line_string(&left), "This is a sp-");
@@ -581,4 +805,109 @@ mod test {
         assert_eq!(line_string(&left), "This is a span of tex-");
         assert_eq!(line_string(&right), "t");
     }
+
+    #[test]
+    fn parse_fg_bg() {
+        let mut colors = Vec::new();
+        let line = parse_colored_line_ex("a#[red:blue]b#[green]c#[:white]d#[]e#[/]f", &mut colors);
+        let spans: Vec<(Option<&str>, Option<&str>, &str)> =
+            line.spans().map(|s| (s.fg(), s.bg(), s.as_str())).collect();
+        assert_eq!(
+            spans,
+            vec![
+                (None, None, "a"),
+                (Some("red"), Some("blue"), "b"),
+                (Some("green"), Some("blue"), "c"),
+                (Some("green"), Some("white"), "d"),
+                (Some("green"), Some("blue"), "e"),
+                (None, None, "f"),
+            ]
+        );
+        assert!(colors.is_empty());
+    }
+
+    #[test]
+    fn parse_effects() {
+        let mut colors = Vec::new();
+        let line =
+            parse_colored_line_ex("a#[~wave]b#[red]c#[:blue~shake]d#[~]e#[]#[]f", &mut colors);
+        let spans: Vec<(Option<&str>, Option<&str>, &str)> = line
+            .spans()
+            .map(|s| (s.fg(), s.effect(), s.as_str()))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (None, None, "a"),
+                (None, Some("wave"), "b"),
+                (Some("red"), Some("wave"), "c"),
+                (Some("red"), Some("shake"), "d"),
+                (Some("red"), None, "e"),
+                (Some("red"), Some("wave"), "f"),
+            ]
+        );
+        assert_eq!(
+            line.spans().nth(3).unwrap().to_string(),
+            "#[red:blue~shake]d"
+        );
+    }
+
+    #[test]
+    fn parse_fg_stack() {
+        let mut colors = vec![Some("blue")];
+        let line = parse_colored_line("a#[red:white]b#[green]c", &mut colors);
+        let spans: Vec<ColoredSpan> = line.spans().cloned().collect();
+        assert_eq!(spans[0].fg(), Some("blue"));
+        assert_eq!(spans[1].bg(), Some("white"));
+        assert_eq!(colors, vec![Some("blue"), Some("red"), Some("green")]);
+    }
+
+    #[test]
+    fn parse_glyphs() {
+        let mut colors = Vec::new();
+        let line = parse_colored_line_ex("#[red]a#{219}b#{heart}#{{c#[[", &mut colors);
+        assert_eq!(line.char_len(), 9);
+        let spans: Vec<ColoredSpan> = line.spans().cloned().collect();
+        assert_eq!(spans[1].glyph(), Some("219"));
+        assert_eq!(spans[1].fg(), Some("red"));
+        assert_eq!(spans[3].glyph(), Some("heart"));
+        assert_eq!(line_string(&line), "a219bheart#{c#[");
+        assert_eq!(
+            line.to_string(),
+            "#[red]a#[red]#{219}#[red]b#[red]#{heart}#[red]#{{#[red]c#[red]#[["
+        );
+        // the text is escaped, so it parses back the same
+        let text = line.to_string();
+        let again = parse_colored_line_ex(&text, &mut Vec::new());
+        assert_eq!(line_string(&again), "a219bheart#{c#[");
+    }
+
+    #[test]
+    fn parse_errors() {
+        let mut colors = Vec::new();
+        let err = try_parse_colored_line("ok #[red", &mut colors).unwrap_err();
+        assert_eq!(err.kind, MarkupErrKind::UnclosedColor);
+        assert_eq!(err.pos, 3);
+
+        let err = try_parse_colored_lines("fine\nbad #{}").unwrap_err();
+        assert_eq!(err.kind, MarkupErrKind::EmptyGlyph);
+        assert_eq!((err.line, err.pos), (1, 4));
+
+        let err = try_parse_colored_lines("#[red]a#[]#[] b").unwrap_err();
+        assert_eq!(err.kind, MarkupErrKind::ExtraPop);
+        assert_eq!(err.pos, 10);
+
+        assert!(try_parse_colored_lines("#[red]a#{1}#[]").is_ok());
+    }
+
+    #[test]
+    fn parse_lenient() {
+        let mut colors = Vec::new();
+        let line = parse_colored_line_ex("a #[red]b #{} #[blue c #{x", &mut colors);
+        assert_eq!(line_string(&line), "a b #{} #[blue c #{x");
+        assert!(line.spans().skip(1).all(|s| s.fg() == Some("red")));
+
+        let line = parse_colored_line_ex("#[]#[]d", &mut colors);
+        assert_eq!(line_string(&line), "d");
+    }
 }
@@ -0,0 +1,165 @@
+use crate::RGBA;
+use std::f32::consts::TAU;
+
+/// What an effect can change about a char when it is printed
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EffectCell {
+    /// false = draw a blank cell (with the bg) instead of the char
+    pub visible: bool,
+    pub fg: Option<RGBA>,
+    pub bg: Option<RGBA>,
+    /// The number of cells to move the char down (negative is up)
+    pub dy: i32,
+}
+
+impl EffectCell {
+    /// Constructs a visible cell with the given colors and no offset
+    pub fn new(fg: Option<RGBA>, bg: Option<RGBA>) -> Self {
+        EffectCell {
+            visible: true,
+            fg,
+            bg,
+            dy: 0,
+        }
+    }
+}
+
+/// An animated effect for a span of text, driven by the elapsed time
+///
+/// In colored markup, use `#[~name]` (or `#[fg:bg~name]`) and `#[]` to end it.
+/// The names are `type` (or `typewriter`), `blink`, `wave` and `shake`, with an
+/// optional speed - e.g. `#[~type=40]` (see [`TextEffect::from_name`]).
+#[derive(Debug, Copy, Clone)]
+pub enum TextEffect {
+    /// Reveals the chars one at a time - chars per second
+    Typewriter(f32),
+    /// Hides the text for the second half of each period - period in seconds
+    Blink(f32),
+    /// Moves the chars up and down in a wave that travels along the text - period in seconds
+    Wave(f32),
+    /// Jitters the chars up and down at random - changes per second
+    Shake(f32),
+    /// Your own effect - called with the time, the index of the char in the span, and the cell to change
+    Custom(fn(f32, usize, &mut EffectCell)),
+}
+
+impl TextEffect {
+    /// Finds the effect for a markup name, e.g. "wave" or "type=40"
+    pub fn from_name(name: &str) -> Option<TextEffect> {
+        let (name, value) = match name.split_once('=') {
+            None => (name, None),
+            Some((name, value)) => (name, Some(value.parse::<f32>().ok()?)),
+        };
+        let effect = match name {
+            "type" | "typewriter" => TextEffect::Typewriter(value.unwrap_or(20.0)),
+            "blink" => TextEffect::Blink(value.unwrap_or(1.0)),
+            "wave" => TextEffect::Wave(value.unwrap_or(1.0)),
+            "shake" => TextEffect::Shake(value.unwrap_or(10.0)),
+            _ => return None,
+        };
+        Some(effect)
+    }
+
+    /// Changes the cell for the char at the index (in the span) at the given time (in seconds)
+    pub fn apply(&self, time: f32, index: usize, cell: &mut EffectCell) {
+        match *self {
+            TextEffect::Typewriter(speed) => {
+                cell.visible = (index as f32) < time * speed;
+            }
+            TextEffect::Blink(period) => {
+                if period > 0.0 && (time / period).fract() >= 0.5 {
+                    cell.visible = false;
+                }
+            }
+            TextEffect::Wave(period) => {
+                // 8 chars per wave
+                let phase = match period > 0.0 {
+                    true => time / period,
+                    false => 0.0,
+                };
+                let angle = (phase - index as f32 / 8.0) * TAU;
+                cell.dy = angle.sin().round() as i32;
+            }
+            TextEffect::Shake(speed) => {
+                let step = (time * speed).max(0.0) as u32;
+                cell.dy = (jitter(step, index as u32) % 3) as i32 - 1;
+            }
+            TextEffect::Custom(func) => func(time, index, cell),
+        }
+    }
+
+    /// The time it takes to show all of the chars of a span with the given length (for typewriter)
+    ///
+    /// Effects that do not end return None.
+    pub fn duration(&self, len: usize) -> Option<f32> {
+        match *self {
+            TextEffect::Typewriter(speed) if speed > 0.0 => Some(len as f32 / speed),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for TextEffect {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (TextEffect::Typewriter(a), TextEffect::Typewriter(b)) => a == b,
+            (TextEffect::Blink(a), TextEffect::Blink(b)) => a == b,
+            (TextEffect::Wave(a), TextEffect::Wave(b)) => a == b,
+            (TextEffect::Shake(a), TextEffect::Shake(b)) => a == b,
+            (TextEffect::Custom(a), TextEffect::Custom(b)) => *a as usize == *b as usize,
+            _ => false,
+        }
+    }
+}
+
+// a cheap hash, so each char shakes on its own
+fn jitter(step: u32, index: u32) -> u32 {
+    let mut h = step.wrapping_mul(0x9E37_79B9) ^ index.wrapping_mul(0x85EB_CA6B);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0xC2B2_AE35);
+    h ^ (h >> 13)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            TextEffect::from_name("type=40"),
+            Some(TextEffect::Typewriter(40.0))
+        );
+        assert_eq!(TextEffect::from_name("wave"), Some(TextEffect::Wave(1.0)));
+        assert_eq!(TextEffect::from_name("wave=x"), None);
+        assert_eq!(TextEffect::from_name("taco"), None);
+    }
+
+    #[test]
+    fn apply() {
+        let mut cell = EffectCell::new(None, None);
+        let typewriter = TextEffect::Typewriter(10.0);
+        typewriter.apply(0.25, 2, &mut cell);
+        assert!(cell.visible);
+        typewriter.apply(0.25, 3, &mut cell);
+        assert!(!cell.visible);
+        assert_eq!(typewriter.duration(5), Some(0.5));
+
+        let mut cell = EffectCell::new(None, None);
+        TextEffect::Blink(1.0).apply(1.25, 0, &mut cell);
+        assert!(cell.visible);
+        TextEffect::Blink(1.0).apply(1.75, 0, &mut cell);
+        assert!(!cell.visible);
+
+        let mut cell = EffectCell::new(None, None);
+        TextEffect::Wave(1.0).apply(0.25, 0, &mut cell);
+        assert_eq!(cell.dy, 1);
+        TextEffect::Wave(1.0).apply(0.25, 4, &mut cell);
+        assert_eq!(cell.dy, -1);
+
+        for i in 0..10 {
+            TextEffect::Shake(10.0).apply(0.5, i, &mut cell);
+            assert!(cell.dy.abs() <= 1);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+/// Finds where words can be hyphenated, using Liang's patterns (as used by TeX)
+///
+/// Use [`Hyphenator::english`] for the bundled US English patterns, or load your own
+/// with [`Hyphenator::new`].  Pass it to [`super::wrap_hyphenated`] or
+/// [`crate::draw::PlainPrinter::hyphenator`] to break long words at syllables.
+#[derive(Debug, Clone)]
+pub struct Hyphenator {
+    patterns: HashMap<String, Vec<u8>>,
+    exceptions: HashMap<String, Vec<usize>>,
+    max_len: usize,
+    left_min: usize,
+    right_min: usize,
+}
+
+impl Hyphenator {
+    /// Constructs a hyphenator from TeX style patterns (e.g. `.ach4 1ba hy3ph`)
+    ///
+    /// Patterns are separated by whitespace and words with hyphens are exceptions
+    /// that are hyphenated exactly as given (e.g. `ta-ble`).  Lines that start with '%' are comments.
+    pub fn new(patterns: &str) -> Self {
+        let mut out = Hyphenator {
+            patterns: HashMap::new(),
+            exceptions: HashMap::new(),
+            max_len: 0,
+            left_min: 2,
+            right_min: 3,
+        };
+        for line in patterns
+            .lines()
+            .filter(|l| !l.trim_start().starts_with('%'))
+        {
+            for item in line.split_whitespace() {
+                if item.contains('-') {
+                    let mut breaks = Vec::new();
+                    for part in item.split('-') {
+                        let last = breaks.last().copied().unwrap_or(0);
+                        breaks.push(last + part.chars().count());
+                    }
+                    breaks.pop();
+                    out.exceptions.insert(item.replace('-', ""), breaks);
+                    continue;
+                }
+
+                let mut letters = String::new();
+                let mut levels = vec![0];
+                for ch in item.chars() {
+                    match ch.to_digit(10) {
+                        Some(level) => *levels.last_mut().unwrap() = level as u8,
+                        None => {
+                            letters.push(ch);
+                            levels.push(0);
+                        }
+                    }
+                }
+                out.max_len = out.max_len.max(letters.chars().count());
+                out.patterns.insert(letters, levels);
+            }
+        }
+        out
+    }
+
+    /// The bundled US English patterns
+    pub fn english() -> Self {
+        Hyphenator::new(include_str!("hyph-en-us.txt"))
+    }
+
+    /// Sets the fewest chars to leave before a hyphen, default=2
+    pub fn left_min(mut self, left_min: usize) -> Self {
+        self.left_min = left_min.max(1);
+        self
+    }
+
+    /// Sets the fewest chars to move after a hyphen, default=3
+    pub fn right_min(mut self, right_min: usize) -> Self {
+        self.right_min = right_min.max(1);
+        self
+    }
+
+    /// Returns the byte offsets in the word where a hyphen can go
+    ///
+    /// Only runs of letters are hyphenated, so punctuation (e.g. "stonemasons'") is left alone.
+    pub fn hyphenate(&self, word: &str) -> Vec<usize> {
+        let mut out = Vec::new();
+        let mut start = None;
+        for (idx, ch) in word
+            .char_indices()
+            .chain(std::iter::once((word.len(), ' ')))
+        {
+            match (ch.is_alphabetic(), start) {
+                (true, None) => start = Some(idx),
+                (false, Some(from)) => {
+                    let run = &word[from..idx];
+                    let offsets: Vec<usize> = run.char_indices().map(|(i, _)| i).collect();
+                    out.extend(self.run_breaks(run).into_iter().map(|i| from + offsets[i]));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        out
+    }
+
+    /// Splits the word at all of the places a hyphen can go
+    pub fn syllables<'a>(&self, word: &'a str) -> Vec<&'a str> {
+        let mut out = Vec::new();
+        let mut last = 0;
+        for idx in self.hyphenate(word) {
+            out.push(&word[last..idx]);
+            last = idx;
+        }
+        out.push(&word[last..]);
+        out
+    }
+
+    // the char indexes in the (all letters) word where a hyphen can go
+    fn run_breaks(&self, word: &str) -> Vec<usize> {
+        let lower = word.to_lowercase();
+        let chars: Vec<char> = lower.chars().collect();
+        let len = chars.len();
+        if len != word.chars().count() || len < self.left_min + self.right_min {
+            return Vec::new();
+        }
+        if let Some(breaks) = self.exceptions.get(&lower) {
+            return breaks.clone();
+        }
+
+        let dotted: Vec<char> = std::iter::once('.')
+            .chain(chars.iter().copied())
+            .chain(std::iter::once('.'))
+            .collect();
+        // levels[i] is the level before dotted[i]
+        let mut levels = vec![0; dotted.len() + 1];
+        let mut key = String::new();
+        for start in 0..dotted.len() {
+            key.clear();
+            for ch in dotted[start..].iter().take(self.max_len) {
+                key.push(*ch);
+                if let Some(pattern) = self.patterns.get(&key) {
+                    for (i, level) in pattern.iter().enumerate() {
+                        levels[start + i] = levels[start + i].max(*level);
+                    }
+                }
+            }
+        }
+
+        // a hyphen before char i of the word is before dotted[i + 1]
+        (self.left_min..=len - self.right_min)
+            .filter(|i| levels[i + 1] % 2 == 1)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        let hyphenator = Hyphenator::new("a1b 2bb\n% 1c\nta-ble")
+            .left_min(1)
+            .right_min(1);
+        assert_eq!(hyphenator.hyphenate("abab"), vec![1, 3]);
+        assert_eq!(hyphenator.hyphenate("abbab"), vec![4]);
+        assert_eq!(hyphenator.hyphenate("table"), vec![2]);
+        assert_eq!(hyphenator.hyphenate("ac"), Vec::<usize>::new());
+        assert_eq!(
+            Hyphenator::new("a1b").hyphenate("abab"),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn english() {
+        let hyphenator = Hyphenator::english();
+        let join = |word: &str| hyphenator.syllables(word).join("-");
+        assert_eq!(join("hyphenation"), "hy-phen-ation");
+        assert_eq!(join("extensive"), "ex-ten-sive");
+        assert_eq!(join("Conflagration"), "Con-fla-gra-tion");
+        assert_eq!(join("stonemasons'"), "stone-ma-sons'");
+        assert_eq!(join("table"), "ta-ble");
+        assert_eq!(join("the"), "the");
+    }
+}
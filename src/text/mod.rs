@@ -1,8 +1,23 @@
 mod plain;
-pub use plain::{wrap_plain, wrap_plain_no_hyphen, RefLine};
+pub use plain::{wrap_hyphenated, wrap_plain, wrap_plain_no_hyphen, RefLine};
 
 mod colored;
 pub use colored::{
-    parse_colored_line, parse_colored_lines, wrap_colored, wrap_colored_no_hyphen, ColoredLine,
-    ColoredSpan,
+    parse_colored_line, parse_colored_line_ex, parse_colored_lines, try_parse_colored_line,
+    try_parse_colored_lines, wrap_colored, wrap_colored_no_hyphen, ColoredLine, ColoredSpan,
+    MarkupErr, MarkupErrKind, SpanColors,
 };
+
+mod rich;
+pub(crate) use colored::wrap_colored_line;
+pub use rich::{escape_markup, RichSpan, RichText};
+
+mod unicode;
+pub(crate) use unicode::{byte_at_col, ends_wide, fix_wide_halves, last_space_before, text_cells};
+pub use unicode::{display_width, grapheme_width};
+
+mod effect;
+pub use effect::{EffectCell, TextEffect};
+
+mod hyphen;
+pub use hyphen::Hyphenator;
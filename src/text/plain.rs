@@ -1,4 +1,6 @@
+use super::{byte_at_col, display_width, ends_wide, grapheme_width, last_space_before, Hyphenator};
 use std::cmp::min;
+use unicode_segmentation::UnicodeSegmentation;
 
 pub struct Line(String, bool);
 
@@ -12,7 +14,7 @@ impl Line {
     }
 
     pub fn char_len(&self) -> usize {
-        self.0.chars().count() + if self.1 { 1 } else { 0 }
+        display_width(&self.0) + if self.1 { 1 } else { 0 }
     }
 
     pub fn has_hyphen(&self) -> bool {
@@ -22,6 +24,38 @@ impl Line {
 
 pub struct RefLine<'a>(&'a str, bool);
 
+const SOFT_HYPHEN: &str = "\u{AD}";
+const ZERO_WIDTH_SPACE: &str = "\u{200B}";
+
+// A place to break a line - the left side ends at left and the right side starts at right
+#[derive(Copy, Clone)]
+struct Break {
+    left: usize,
+    right: usize,
+    // add a hyphen to the left side (soft hyphens)
+    hyphen: bool,
+    // the break is a space between words
+    space: bool,
+}
+
+impl Break {
+    fn new(left: usize, right: usize, hyphen: bool, space: bool) -> Self {
+        Break {
+            left,
+            right,
+            hyphen,
+            space,
+        }
+    }
+}
+
+fn is_letter(grapheme: Option<&str>) -> bool {
+    grapheme
+        .and_then(|g| g.chars().next())
+        .map(|ch| ch.is_alphanumeric())
+        .unwrap_or(false)
+}
+
 impl<'a> RefLine<'a> {
     pub fn new(t: &'a str) -> Self {
         RefLine(t, false)
@@ -32,7 +66,7 @@ impl<'a> RefLine<'a> {
     }
 
     pub fn char_len(&self) -> usize {
-        self.0.chars().count() + if self.1 { 1 } else { 0 }
+        display_width(self.0) + if self.1 { 1 } else { 0 }
     }
 
     pub fn has_hyphen(&self) -> bool {
@@ -52,12 +86,70 @@ impl<'a> RefLine<'a> {
         self
     }
 
+    // the index is in cells, not chars or bytes
     fn last_break_before(&self, char_idx: usize) -> Option<usize> {
-        let idx = self.0.char_indices().nth(char_idx).map(|(i, _)| i).unwrap();
-        match self.0[..idx].rmatch_indices(' ').next() {
-            None => None,
-            Some((idx, _)) => Some(idx),
+        last_space_before(self.0, char_idx)
+    }
+
+    // the last place the line can break and keep the left side in the width
+    fn last_break_within(&self, width: usize) -> Option<Break> {
+        let mut best = None;
+        let mut col = 0;
+        let mut prev = None;
+        let mut graphemes = self.0.grapheme_indices(true).peekable();
+        while let Some((idx, grapheme)) = graphemes.next() {
+            if col > width {
+                break;
+            }
+            let next = graphemes.peek().map(|(_, g)| *g);
+            let found = match grapheme {
+                " " => Some((Break::new(idx, idx + 1, false, true), col)),
+                ZERO_WIDTH_SPACE => Some((Break::new(idx, idx + 3, false, false), col)),
+                SOFT_HYPHEN => Some((Break::new(idx, idx + 2, true, false), col + 1)),
+                "-" if is_letter(prev) && is_letter(next) => {
+                    Some((Break::new(idx + 1, idx + 1, false, false), col + 1))
+                }
+                _ => None,
+            };
+            if let Some((brk, len)) = found {
+                if len <= width {
+                    best = Some(brk);
+                }
+            }
+            col += grapheme_width(grapheme);
+            prev = Some(grapheme);
         }
+        best
+    }
+
+    // true if the line has hyphens, soft hyphens, or zero width spaces
+    fn has_breaks(&self) -> bool {
+        self.0.contains(['-', '\u{AD}', '\u{200B}'])
+    }
+
+    // the byte index of the last syllable break where the left side and a hyphen fit in the width
+    fn syllable_within(&self, width: usize, hyphenator: Option<&Hyphenator>) -> Option<usize> {
+        hyphenator?
+            .hyphenate(self.0)
+            .into_iter()
+            .rev()
+            .find(|idx| display_width(&self.0[..*idx]) < width)
+    }
+
+    fn split_at_break(&self, brk: Break) -> (Self, Self) {
+        let left = RefLine::new(&self.0[..brk.left]);
+        let left = match brk.hyphen {
+            true => left.with_hyphen(),
+            false => left,
+        };
+        (left, RefLine::new(&self.0[brk.right..]))
+    }
+
+    fn hyphenate_at_byte(&self, idx: usize) -> (Self, Self) {
+        (
+            RefLine::new(&self.0[..idx]).with_hyphen(),
+            RefLine::new(&self.0[idx..]),
+        )
     }
 
     fn first_word(&self) -> Self {
@@ -72,7 +164,12 @@ impl<'a> RefLine<'a> {
     // }
 
     fn hyphenate_at_char(&self, char_idx: usize) -> (Self, Self) {
-        let idx = self.0.char_indices().nth(char_idx).map(|(i, _)| i).unwrap();
+        // wide (CJK) text can break anywhere, so it does not need a hyphen (and gets the extra cell)
+        let idx = byte_at_col(self.0, char_idx + 1);
+        if ends_wide(&self.0[..idx]) {
+            return (RefLine::new(&self.0[..idx]), RefLine::new(&self.0[idx..]));
+        }
+        let idx = byte_at_col(self.0, char_idx);
         (
             RefLine::new(&self.0[..idx]).with_hyphen(),
             RefLine::new(&self.0[idx..]),
@@ -80,12 +177,12 @@ impl<'a> RefLine<'a> {
     }
 
     fn split_at_char(&self, char_idx: usize) -> (Self, Self) {
-        let idx = self.0.char_indices().nth(char_idx).map(|(i, _)| i).unwrap();
+        let idx = byte_at_col(self.0, char_idx);
         (RefLine::new(&self.0[..idx]), RefLine::new(&self.0[idx..]))
     }
 
     fn split_at_space(&self, char_idx: usize) -> (Self, Self) {
-        let idx = self.0.char_indices().nth(char_idx).map(|(i, _)| i).unwrap();
+        let idx = byte_at_col(self.0, char_idx);
         (
             RefLine::new(&self.0[..idx]),
             RefLine::new(&self.0[idx + 1..]),
@@ -102,7 +199,28 @@ impl<'a> ToString for RefLine<'a> {
     }
 }
 
+/// Wraps the text into lines that are at most the given width
+///
+/// Lines break at spaces, after hyphens in words, at soft hyphens (U+00AD, which add a hyphen)
+/// and at zero width spaces (U+200B).  Words that do not fit are hyphenated wherever needed,
+/// use [`wrap_hyphenated`] to only hyphenate at syllables.
 pub fn wrap_plain<'a>(limit: usize, text: &'a str) -> Vec<RefLine<'a>> {
+    wrap_with(limit, text, None)
+}
+
+/// Wraps the text like [`wrap_plain`], but uses the hyphenator to break long words at syllables
+///
+/// A word is only hyphenated at an arbitrary position if it is too long for a line and
+/// has no syllable breaks that fit.
+pub fn wrap_hyphenated<'a>(
+    limit: usize,
+    text: &'a str,
+    hyphenator: &Hyphenator,
+) -> Vec<RefLine<'a>> {
+    wrap_with(limit, text, Some(hyphenator))
+}
+
+fn wrap_with<'a>(limit: usize, text: &'a str, hyphenator: Option<&Hyphenator>) -> Vec<RefLine<'a>> {
     // println!("--------------------------------------");
     // println!("WRAP - {}: '{}'", limit, text);
 
@@ -110,37 +228,46 @@ pub fn wrap_plain<'a>(limit: usize, text: &'a str) -> Vec<RefLine<'a>> {
 
     for line in text.split('\n') {
         let mut current = RefLine::new(line);
-        let mut i = 0;
 
         while current.char_len() > limit {
-            i += 1;
-            if i > 10 {
-                break;
-            }
+            // stop if nothing fits (e.g. a wide glyph in a 1 cell limit)
+            let before = current.0.len();
 
-            match current.last_break_before(limit + 1) {
+            match current.last_break_within(limit) {
                 None => {
                     let first_word = current.first_word();
-                    let first_word_len = first_word.char_len();
 
                     // println!("too long - {}", first_word.0);
 
-                    let keep_len = min(limit - 1, first_word_len - 2);
-                    let (left, right) = current.hyphenate_at_char(keep_len);
+                    let (left, right) = match first_word.syllable_within(limit, hyphenator) {
+                        Some(idx) => current.hyphenate_at_byte(idx),
+                        None => {
+                            let first_word_len = first_word.char_len();
+                            let keep_len =
+                                min(limit.saturating_sub(1), first_word_len.saturating_sub(2));
+                            current.hyphenate_at_char(keep_len)
+                        }
+                    };
                     output.push(left);
                     current = right;
                 }
-                Some(break_index) => {
-                    let (mut left, mut right) = current.split_at_space(break_index);
+                Some(brk) => {
+                    let (mut left, mut right) = current.split_at_break(brk);
                     let left_len = left.char_len();
                     let line_left = limit.saturating_sub(left_len).saturating_sub(1);
+                    let next_word = right.first_word();
 
                     // println!(
                     //     " - left={}, line_left={}, right={}",
                     //     left.0, line_left, right.0
                     // );
-                    if line_left >= 4 {
-                        let next_word = right.first_word();
+                    // only whole words (after a space) are hyphenated to fill the line
+                    if !brk.space || next_word.has_breaks() {
+                    } else if hyphenator.is_some() {
+                        if let Some(idx) = next_word.syllable_within(line_left, hyphenator) {
+                            (left, right) = current.hyphenate_at_byte(brk.right + idx);
+                        }
+                    } else if line_left >= 4 {
                         let next_word_len = next_word.char_len();
 
                         // println!(" - : next_word={}, len={}", next_word.0, next_word_len);
@@ -148,13 +275,16 @@ pub fn wrap_plain<'a>(limit: usize, text: &'a str) -> Vec<RefLine<'a>> {
                         if next_word_len >= 6 {
                             let keep_len = min(line_left, next_word_len - 2);
                             // println!(" - : hyphen! keep={}", keep_len);
-                            (left, right) = current.hyphenate_at_char(break_index + keep_len);
+                            (left, right) = current.hyphenate_at_char(left_len + keep_len);
                         }
                     }
                     output.push(left);
                     current = right;
                 }
             }
+            if current.0.len() >= before {
+                break;
+            }
         }
 
         if current.char_len() > 0 {
@@ -172,13 +302,10 @@ pub fn wrap_plain_no_hyphen<'a>(limit: usize, text: &'a str) -> Vec<RefLine<'a>>
 
     for line in text.split('\n') {
         let mut current = RefLine::new(line);
-        let mut i = 0;
 
         while current.char_len() > limit {
-            i += 1;
-            if i > 10 {
-                break;
-            }
+            // stop if nothing fits (e.g. a wide glyph in a 1 cell limit)
+            let before = current.0.len();
 
             match current.last_break_before(limit + 1) {
                 None => {
@@ -217,6 +344,9 @@ pub fn wrap_plain_no_hyphen<'a>(limit: usize, text: &'a str) -> Vec<RefLine<'a>>
                     current = right;
                 }
             }
+            if current.0.len() >= before {
+                break;
+            }
         }
 
         if current.char_len() > 0 {
@@ -225,3 +355,18 @@ pub fn wrap_plain_no_hyphen<'a>(limit: usize, text: &'a str) -> Vec<RefLine<'a>>
     }
     output
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wrap_narrow() {
+        // no room for anything - must not underflow or loop forever, the text is kept
+        let lines = wrap_plain(0, "taco casa");
+        assert_eq!(lines.last().map(|line| line.as_str()), Some("taco casa"));
+
+        let lines: Vec<&str> = wrap_plain(1, "a b").iter().map(|l| l.as_str()).collect();
+        assert_eq!(lines, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,462 @@
+use super::{display_width, parse_colored_lines, wrap_plain, TextEffect};
+use crate::simple::{parse_glyph_code, Glyph};
+use crate::{to_rgba, RGBA};
+use std::ops::{Add, AddAssign};
+
+// stands in for inline glyphs when wrapping (it is not a space, so it is treated as part of a word)
+const GLYPH_MARKER: char = '\u{FFFC}';
+
+/// Builds a [`RichText`] from colored markup, using `format!` arguments
+///
+/// Use [`escape_markup`] on any arguments that should not be read as markup (e.g. player names).
+///
+/// ```
+/// use conapp::rich_text;
+/// let text = rich_text!("You hit the #[#f00]{}#[] for {} damage.", "orc", 5);
+/// assert_eq!(text.to_plain(), "You hit the orc for 5 damage.");
+/// ```
+#[macro_export]
+macro_rules! rich_text {
+    ($($arg:tt)*) => {
+        $crate::text::RichText::from_markup(&format!($($arg)*))
+    };
+}
+
+/// Escapes text so that it prints as is when used in colored markup
+pub fn escape_markup(text: &str) -> String {
+    text.replace("#[", "#[[").replace("#{", "#{{")
+}
+
+/// A piece of [`RichText`] that has a single style
+#[derive(Debug, Clone, PartialEq)]
+pub struct RichSpan {
+    text: String,
+    fg: Option<RGBA>,
+    bg: Option<RGBA>,
+    effect: Option<TextEffect>,
+    glyph: Option<Glyph>,
+    // a glyph name, found when the span is printed (see crate::GlyphRegistry)
+    glyph_name: Option<String>,
+}
+
+impl RichSpan {
+    /// Constructs a span of text with no colors (the printer colors are used)
+    pub fn new(text: &str) -> Self {
+        RichSpan {
+            text: text.to_owned(),
+            fg: None,
+            bg: None,
+            effect: None,
+            glyph: None,
+            glyph_name: None,
+        }
+    }
+
+    /// Constructs a span that draws a single glyph, instead of text
+    pub fn glyph(glyph: Glyph) -> Self {
+        RichSpan {
+            text: String::new(),
+            fg: None,
+            bg: None,
+            effect: None,
+            glyph: Some(glyph),
+            glyph_name: None,
+        }
+    }
+
+    /// Constructs a span that draws a named glyph, instead of text
+    ///
+    /// The name is looked up when the span is printed, like the `#{name}` markup of
+    /// [`crate::draw::ColoredPrinter`], so the glyph's default colors are used where the span has none.
+    pub fn named_glyph(name: &str) -> Self {
+        RichSpan {
+            text: String::new(),
+            fg: None,
+            bg: None,
+            effect: None,
+            glyph: None,
+            glyph_name: Some(name.to_owned()),
+        }
+    }
+
+    /// Sets the fg of the span
+    pub fn fg(mut self, fg: RGBA) -> Self {
+        self.fg = Some(fg);
+        self
+    }
+
+    /// Sets the bg of the span
+    pub fn bg(mut self, bg: RGBA) -> Self {
+        self.bg = Some(bg);
+        self
+    }
+
+    /// Sets the animated effect of the span
+    pub fn effect(mut self, effect: TextEffect) -> Self {
+        self.effect = Some(effect);
+        self
+    }
+
+    /// The text of the span (empty for glyph spans)
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The fg of the span, None = use the printer fg
+    pub fn get_fg(&self) -> Option<RGBA> {
+        self.fg
+    }
+
+    /// The bg of the span, None = use the printer bg
+    pub fn get_bg(&self) -> Option<RGBA> {
+        self.bg
+    }
+
+    /// The animated effect of the span (if any)
+    pub fn get_effect(&self) -> Option<TextEffect> {
+        self.effect
+    }
+
+    /// The glyph this span draws (if it is a glyph span)
+    pub fn get_glyph(&self) -> Option<Glyph> {
+        self.glyph
+    }
+
+    /// The name of the glyph this span draws (if it is a named glyph span)
+    pub fn get_glyph_name(&self) -> Option<&str> {
+        self.glyph_name.as_deref()
+    }
+
+    /// Returns true if the span draws a single glyph (by number or name), instead of text
+    pub fn is_glyph(&self) -> bool {
+        self.glyph.is_some() || self.glyph_name.is_some()
+    }
+
+    /// Length of the span in cells (wide chars count as 2)
+    pub fn char_len(&self) -> usize {
+        match self.is_glyph() {
+            true => 1,
+            false => display_width(&self.text),
+        }
+    }
+
+    /// Returns a span of the given text with the same colors
+    fn with_text(&self, text: &str) -> Self {
+        RichSpan {
+            text: text.to_owned(),
+            fg: self.fg,
+            bg: self.bg,
+            effect: self.effect,
+            glyph: None,
+            glyph_name: None,
+        }
+    }
+
+    fn same_style(&self, other: &RichSpan) -> bool {
+        !self.is_glyph()
+            && !other.is_glyph()
+            && self.fg == other.fg
+            && self.bg == other.bg
+            && self.effect == other.effect
+    }
+}
+
+/// Styled text that owns its contents, so it can be kept (e.g. in a message log) and built up in code
+///
+/// The text can have newlines.  Print it with [`crate::draw::ColoredPrinter::print_rich`]
+/// or [`crate::draw::ColoredPrinter::wrap_rich`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RichText {
+    spans: Vec<RichSpan>,
+}
+
+impl RichText {
+    /// Constructs an empty text
+    pub fn new() -> Self {
+        RichText { spans: Vec::new() }
+    }
+
+    /// Constructs a text with no colors
+    pub fn plain(text: &str) -> Self {
+        RichText::new().with(RichSpan::new(text))
+    }
+
+    /// Constructs a text from colored markup (see [`super::parse_colored_line_ex`])
+    ///
+    /// Colors are converted with [`crate::to_rgba`] and effects with [`TextEffect::from_name`].
+    /// Inline glyphs that are not numbers keep their names (see [`RichSpan::named_glyph`]).
+    pub fn from_markup(markup: &str) -> Self {
+        let mut out = RichText::new();
+        for (i, line) in parse_colored_lines(markup).iter().enumerate() {
+            if i > 0 {
+                out.push_str("\n");
+            }
+            for span in line.spans() {
+                let mut rich = match span.glyph() {
+                    None => RichSpan::new(span.as_str()),
+                    Some(code) => match parse_glyph_code(code) {
+                        Some(glyph) => RichSpan::glyph(glyph),
+                        None => RichSpan::named_glyph(code),
+                    },
+                };
+                rich.fg = span.fg().and_then(to_rgba);
+                rich.bg = span.bg().and_then(to_rgba);
+                rich.effect = span.effect().and_then(TextEffect::from_name);
+                out.push(rich);
+            }
+        }
+        out
+    }
+
+    /// Adds the span and returns self (for building in a single expression)
+    pub fn with(mut self, span: RichSpan) -> Self {
+        self.push(span);
+        self
+    }
+
+    /// Adds a span to the end, joining it with the last span if they have the same style
+    pub fn push(&mut self, span: RichSpan) {
+        if !span.is_glyph() && span.text.is_empty() {
+            return;
+        }
+        match self.spans.last_mut() {
+            Some(last) if last.same_style(&span) => last.text.push_str(&span.text),
+            _ => self.spans.push(span),
+        }
+    }
+
+    /// Adds text with no colors to the end
+    pub fn push_str(&mut self, text: &str) {
+        self.push(RichSpan::new(text));
+    }
+
+    /// Adds all of the spans of the other text to the end
+    pub fn append(&mut self, other: &RichText) {
+        for span in other.spans.iter() {
+            self.push(span.clone());
+        }
+    }
+
+    /// The spans that make up the text
+    pub fn spans(&self) -> impl Iterator<Item = &RichSpan> {
+        self.spans.iter()
+    }
+
+    /// Returns true if there is no text
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Length of the text in cells (glyphs count as 1, wide chars as 2)
+    pub fn char_len(&self) -> usize {
+        self.spans.iter().map(|s| s.char_len()).sum()
+    }
+
+    /// The width of the widest line in cells
+    pub fn width(&self) -> usize {
+        self.lines().iter().map(|l| l.char_len()).max().unwrap_or(0)
+    }
+
+    /// The number of lines in the text
+    pub fn height(&self) -> usize {
+        self.lines().len()
+    }
+
+    /// The text without any styles (glyphs are left out)
+    pub fn to_plain(&self) -> String {
+        self.spans.iter().map(|s| s.as_str()).collect()
+    }
+
+    /// Splits the text at the newlines
+    pub fn lines(&self) -> Vec<RichText> {
+        let mut out = vec![RichText::new()];
+        for span in self.spans.iter() {
+            if span.is_glyph() {
+                out.last_mut().unwrap().push(span.clone());
+                continue;
+            }
+            for (i, part) in span.text.split('\n').enumerate() {
+                if i > 0 {
+                    out.push(RichText::new());
+                }
+                out.last_mut().unwrap().push(span.with_text(part));
+            }
+        }
+        out
+    }
+
+    /// Wraps the text into lines that are at most the given width
+    ///
+    /// This uses the same rules as [`super::wrap_plain`], including the added hyphens.
+    pub fn wrap(&self, width: usize) -> Vec<RichText> {
+        // each char in the flat text maps to a (span, char) in the styled text
+        let mut flat = String::new();
+        let mut sources: Vec<(usize, char)> = Vec::new();
+        for (i, span) in self.spans.iter().enumerate() {
+            match span.is_glyph() {
+                true => {
+                    flat.push(GLYPH_MARKER);
+                    sources.push((i, GLYPH_MARKER));
+                }
+                false => {
+                    flat.push_str(&span.text);
+                    sources.extend(span.text.chars().map(|ch| (i, ch)));
+                }
+            }
+        }
+
+        // the lines come back in order, so find each one after the end of the last
+        let mut out = Vec::new();
+        let mut byte_pos = 0;
+        let mut char_pos = 0;
+        for line in wrap_plain(width, &flat) {
+            let skipped = flat[byte_pos..].find(line.as_str()).unwrap_or(0);
+            let start = char_pos + flat[byte_pos..byte_pos + skipped].chars().count();
+            let len = line.as_str().chars().count();
+            byte_pos += skipped + line.as_str().len();
+            char_pos = start + len;
+
+            let mut rich = RichText::new();
+            let mut buf = [0; 4];
+            for &(i, ch) in sources[start..start + len].iter() {
+                let span = &self.spans[i];
+                match span.is_glyph() {
+                    true => rich.push(span.clone()),
+                    false => rich.push(span.with_text(ch.encode_utf8(&mut buf))),
+                }
+            }
+            if line.has_hyphen() {
+                let last = sources[..start + len].last().map(|(i, _)| &self.spans[*i]);
+                match last {
+                    Some(span) => rich.push(span.with_text("-")),
+                    None => rich.push_str("-"),
+                }
+            }
+            out.push(rich);
+        }
+        out
+    }
+}
+
+impl From<&str> for RichText {
+    fn from(text: &str) -> Self {
+        RichText::plain(text)
+    }
+}
+
+impl From<RichSpan> for RichText {
+    fn from(span: RichSpan) -> Self {
+        RichText::new().with(span)
+    }
+}
+
+impl Add<RichText> for RichText {
+    type Output = RichText;
+
+    fn add(mut self, rhs: RichText) -> Self::Output {
+        self.append(&rhs);
+        self
+    }
+}
+
+impl Add<RichSpan> for RichText {
+    type Output = RichText;
+
+    fn add(self, rhs: RichSpan) -> Self::Output {
+        self.with(rhs)
+    }
+}
+
+impl Add<&str> for RichText {
+    type Output = RichText;
+
+    fn add(mut self, rhs: &str) -> Self::Output {
+        self.push_str(rhs);
+        self
+    }
+}
+
+impl AddAssign<RichText> for RichText {
+    fn add_assign(&mut self, rhs: RichText) {
+        self.append(&rhs);
+    }
+}
+
+impl AddAssign<RichSpan> for RichText {
+    fn add_assign(&mut self, rhs: RichSpan) {
+        self.push(rhs);
+    }
+}
+
+impl AddAssign<&str> for RichText {
+    fn add_assign(&mut self, rhs: &str) {
+        self.push_str(rhs);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RED: RGBA = RGBA::rgb(255, 0, 0);
+    const BLUE: RGBA = RGBA::rgb(0, 0, 255);
+
+    #[test]
+    fn build_and_measure() {
+        let mut text = RichText::plain("You hit the ") + RichSpan::new("orc").fg(RED) + "!";
+        text += RichSpan::glyph(3).fg(BLUE);
+        text += "\nok";
+
+        assert_eq!(text.spans().count(), 5);
+        assert_eq!(text.to_plain(), "You hit the orc!\nok");
+        assert_eq!(text.width(), 17);
+        assert_eq!(text.height(), 2);
+        assert_eq!(text.lines()[0].spans().nth(1).unwrap().get_fg(), Some(RED));
+    }
+
+    #[test]
+    fn from_markup() {
+        let text = rich_text!("a #[#f00:#00f]{}#{{219}}#[] c", escape_markup("#[b]"));
+        let spans: Vec<&RichSpan> = text.spans().collect();
+        assert_eq!(spans.len(), 4);
+        assert_eq!(spans[1].as_str(), "#[b]");
+        assert_eq!(spans[1].get_fg(), Some(RED));
+        assert_eq!(spans[1].get_bg(), Some(BLUE));
+        assert_eq!(spans[2].get_glyph(), Some(219));
+        assert_eq!(spans[3].get_fg(), None);
+
+        let text = RichText::from_markup("#[#f00]#{player}#[] wins");
+        let span = text.spans().next().unwrap();
+        assert_eq!(span.get_glyph_name(), Some("player"));
+        assert_eq!(span.get_fg(), Some(RED));
+        assert_eq!(text.char_len(), 6);
+    }
+
+    #[test]
+    fn wrap() {
+        let text = RichText::plain("taco ")
+            + RichSpan::new("casa is").fg(RED)
+            + " a great fast food place";
+        let lines = text.wrap(10);
+        let plain: Vec<String> = lines.iter().map(|l| l.to_plain()).collect();
+        assert_eq!(plain, vec!["taco casa", "is a great", "fast food", "place"]);
+        assert_eq!(lines[0].spans().nth(1).unwrap().get_fg(), Some(RED));
+        assert_eq!(lines[1].spans().next().unwrap().get_fg(), Some(RED));
+
+        let lines =
+            (RichText::from(RichSpan::glyph(1)) + RichText::plain("supercalafragalistic")).wrap(10);
+        assert_eq!(lines[0].char_len(), 10);
+        assert_eq!(lines[0].spans().next().unwrap().get_glyph(), Some(1));
+        assert_eq!(lines[0].to_plain(), "supercal-");
+    }
+
+    #[test]
+    fn wrap_repeated_lines() {
+        let text = RichText::plain("été été\n") + RichSpan::new("été").fg(RED);
+        let lines = text.wrap(3);
+        let plain: Vec<String> = lines.iter().map(|l| l.to_plain()).collect();
+        assert_eq!(plain, vec!["été", "été", "été"]);
+        assert_eq!(lines[1].spans().next().unwrap().get_fg(), None);
+        assert_eq!(lines[2].spans().next().unwrap().get_fg(), Some(RED));
+    }
+}
@@ -0,0 +1,133 @@
+use crate::simple::{Glyph, GLYPH_INDEX_MASK, WIDE_CONTINUATION, WIDE_GLYPH};
+use std::cmp::min;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// The number of cells the grapheme takes when printed - 0, 1, or 2 (East Asian wide)
+///
+/// Soft hyphens (U+00AD) take 0 cells, they are only printed (as '-') when a line breaks there.
+pub fn grapheme_width(grapheme: &str) -> usize {
+    match grapheme {
+        "\u{AD}" => 0,
+        _ => min(grapheme.width(), 2),
+    }
+}
+
+/// The number of cells the text takes when printed
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// The byte index of the first grapheme that does not fit in the given number of cells
+///
+/// A wide grapheme that would be cut by the column is not included.
+pub(crate) fn byte_at_col(text: &str, col: usize) -> usize {
+    let mut width = 0;
+    for (idx, grapheme) in text.grapheme_indices(true) {
+        width += grapheme_width(grapheme);
+        if width > col {
+            return idx;
+        }
+    }
+    text.len()
+}
+
+/// The column of the last space that starts before the given column
+pub(crate) fn last_space_before(text: &str, col: usize) -> Option<usize> {
+    let mut width = 0;
+    let mut best = None;
+    for grapheme in text.graphemes(true) {
+        if width >= col {
+            break;
+        }
+        if grapheme == " " {
+            best = Some(width);
+        }
+        width += grapheme_width(grapheme);
+    }
+    best
+}
+
+/// Returns true if the text ends with a wide grapheme (so a hyphen is not needed when breaking there)
+pub(crate) fn ends_wide(text: &str) -> bool {
+    text.graphemes(true)
+        .next_back()
+        .map(|g| grapheme_width(g) > 1)
+        .unwrap_or(false)
+}
+
+/// The char and glyph flags to print in each cell of the text
+///
+/// Each grapheme is printed using its first char.  Wide graphemes take 2 cells,
+/// with [`WIDE_GLYPH`] and [`WIDE_CONTINUATION`] flags.  Zero width graphemes are skipped.
+pub(crate) fn text_cells(text: &str) -> Vec<(char, Glyph)> {
+    let mut cells = Vec::with_capacity(text.len());
+    for grapheme in text.graphemes(true) {
+        let ch = match grapheme.chars().next() {
+            None => continue,
+            Some(ch) => ch,
+        };
+        match grapheme_width(grapheme) {
+            0 => {}
+            1 => cells.push((ch, 0)),
+            _ => {
+                cells.push((ch, WIDE_GLYPH));
+                cells.push((ch, WIDE_CONTINUATION));
+            }
+        }
+    }
+    cells
+}
+
+/// Blanks any half of a wide glyph that lost its other half (e.g. when text is cut)
+pub(crate) fn fix_wide_halves<T>(
+    items: &mut [T],
+    flags: impl Fn(&T) -> Glyph,
+    blank: impl Fn(&mut T),
+) {
+    let all: Vec<Glyph> = items.iter().map(|i| flags(i) & !GLYPH_INDEX_MASK).collect();
+    for (i, item) in items.iter_mut().enumerate() {
+        let broken = match all[i] {
+            WIDE_GLYPH => all.get(i + 1) != Some(&WIDE_CONTINUATION),
+            WIDE_CONTINUATION => i == 0 || all[i - 1] != WIDE_GLYPH,
+            _ => false,
+        };
+        if broken {
+            blank(item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn widths() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("こんにちは!"), 11);
+        assert_eq!(display_width("e\u{301}te\u{301}"), 3);
+        assert_eq!(byte_at_col("こんにちは", 3), 3);
+        assert_eq!(byte_at_col("こんにちは", 4), 6);
+        assert_eq!(last_space_before("真棒 abc de", 8), Some(4));
+        assert_eq!(last_space_before("真棒 abc de", 4), None);
+    }
+
+    #[test]
+    fn cells() {
+        let cells = text_cells("a真e\u{301}");
+        assert_eq!(
+            cells,
+            vec![
+                ('a', 0),
+                ('真', WIDE_GLYPH),
+                ('真', WIDE_CONTINUATION),
+                ('e', 0)
+            ]
+        );
+
+        let mut cut = cells[..2].to_vec();
+        fix_wide_halves(&mut cut, |c| c.1, |c| *c = (' ', 0));
+        assert_eq!(cut, vec![('a', 0), (' ', 0)]);
+    }
+}